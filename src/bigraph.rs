@@ -0,0 +1,97 @@
+use num::PrimInt;
+
+use crate::{GraphMatrix, GraphMatrixError};
+
+/// A BiGraphMatrix stores a GraphMatrix alongside its transpose, so both out-neighbors
+/// (CSR) and in-neighbors (CSC) of a vertex are available as slices.
+#[derive(Debug)]
+pub struct BiGraphMatrix<T> {
+    fwd: GraphMatrix<T>,
+    bwd: GraphMatrix<T>,
+}
+
+impl<T> BiGraphMatrix<T> where T: PrimInt {
+
+    pub fn new(g: GraphMatrix<T>) -> Result<Self, GraphMatrixError> {
+        let bwd = g.transpose()?;
+        Ok(BiGraphMatrix {fwd: g, bwd})
+    }
+
+    pub fn from_edgelist(edgelist: Vec<(T, T)>) -> Result<Self, GraphMatrixError> {
+        Self::new(GraphMatrix::from_edgelist(edgelist)?)
+    }
+
+    /// The row-oriented (out-neighbor) matrix.
+    pub fn forward(&self) -> &GraphMatrix<T> {
+        &self.fwd
+    }
+
+    /// The column-oriented (in-neighbor) matrix, i.e. the transpose of `forward()`.
+    pub fn backward(&self) -> &GraphMatrix<T> {
+        &self.bwd
+    }
+
+    pub fn dims(&self) -> (usize, usize) {
+        self.fwd.dims()
+    }
+
+    pub fn ne(&self) -> usize {
+        self.fwd.ne()
+    }
+
+    pub fn out_neighbors(&self, v: T) -> Result<&[T], GraphMatrixError> {
+        self.fwd.row(v)
+    }
+
+    pub fn in_neighbors(&self, v: T) -> Result<&[T], GraphMatrixError> {
+        self.bwd.row(v)
+    }
+
+    pub fn out_degree(&self, v: T) -> Result<usize, GraphMatrixError> {
+        self.fwd.row_len(v)
+    }
+
+    pub fn in_degree(&self, v: T) -> Result<usize, GraphMatrixError> {
+        self.bwd.row_len(v)
+    }
+
+    pub fn has_index(&self, r: T, c: T) -> Result<bool, GraphMatrixError> {
+        self.fwd.has_index(r, c)
+    }
+
+    /// Splits into the forward and backward matrices.
+    pub fn into_parts(self) -> (GraphMatrix<T>, GraphMatrix<T>) {
+        (self.fwd, self.bwd)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn sample() -> BiGraphMatrix<u32> {
+        let edges = vec![(0, 1), (0, 3), (1, 2), (2, 0), (2, 3), (3, 3), (4, 1)];
+        BiGraphMatrix::from_edgelist(edges).unwrap()
+    }
+
+    #[test]
+    fn in_neighbors_are_out_neighbors_of_the_transpose() {
+        let g = sample();
+        let t = g.forward().transpose().unwrap();
+        for v in 0..5 {
+            let sources: Vec<u32> =
+                g.forward().iter().filter(|&(_, d)| d == v).map(|(s, _)| s).collect();
+            assert_eq!(g.in_neighbors(v).unwrap(), sources);
+            assert_eq!(g.in_neighbors(v).unwrap(), t.row(v).unwrap());
+            assert_eq!(g.in_degree(v).unwrap(), sources.len());
+        }
+    }
+
+    #[test]
+    fn transpose_is_an_involution() {
+        let g = sample().forward().transpose().unwrap().transpose().unwrap();
+        let expected: Vec<(u32, u32)> = sample().forward().iter().collect();
+        assert_eq!(g.dims(), (5, 5));
+        assert_eq!(g.iter().collect::<Vec<_>>(), expected);
+    }
+}
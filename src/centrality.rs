@@ -0,0 +1,464 @@
+//! Betweenness, closeness and harmonic centrality over a square GraphMatrix, following
+//! out-edges. Every measure runs one search per source vertex. Each search reads rows
+//! directly from the matrix and needs only O(V) scratch space. With the `rayon` feature, the
+//! `par_` variants spread the sources across the thread pool. Betweenness can instead be
+//! estimated from a few sources, drawn at random by `sample_pivots`.
+//!
+//! Betweenness counts ordered pairs `(s, t)`, so on an undirected graph (a symmetric matrix)
+//! every score is twice the conventional undirected value.
+
+use std::collections::BinaryHeap;
+
+use num::{PrimInt, Zero};
+use rand::seq::index::sample;
+use rand::Rng;
+#[cfg(feature = "rayon")]
+use rayon::prelude::*;
+
+use crate::shortest_paths::{check_nonnegative, check_weights, HeapEntry};
+use crate::{from_usize, index, Bitset, GraphMatrix, GraphMatrixError};
+
+/// Returns the sources to search from, and the factor that scales their summed
+/// dependencies up to an estimate over all sources.
+fn sources<T: PrimInt>(
+    g: &GraphMatrix<T>,
+    pivots: Option<&[T]>,
+) -> Result<(Vec<usize>, f64), GraphMatrixError> {
+    let n = g.nv()?;
+    match pivots {
+        None => Ok(((0..n).collect(), 1.0)),
+        Some([]) => Err(GraphMatrixError::EmptyInput),
+        Some(p) => {
+            let s = p.iter().map(|&v| g.vertex(v)).collect::<Result<Vec<_>, _>>()?;
+            // a repeated pivot would be counted twice and skew the estimate.
+            let mut seen = Bitset::new(n);
+            if !s.iter().all(|&v| seen.insert(v)) {
+                return Err(GraphMatrixError::invalid_parameter("pivots must be distinct"))
+            }
+            let scale = n as f64 / s.len() as f64;
+            Ok((s, scale))
+        }
+    }
+}
+
+/// Draws `k` distinct vertices of `g` uniformly at random, to use as the `pivots` of an
+/// approximate betweenness computation. Seeding `rng` makes the choice reproducible.
+pub fn sample_pivots<T, R>(
+    g: &GraphMatrix<T>,
+    k: usize,
+    rng: &mut R,
+) -> Result<Vec<T>, GraphMatrixError>
+where
+    T: PrimInt,
+    R: Rng + ?Sized,
+{
+    let n = g.nv()?;
+    if k == 0 || k > n {
+        return Err(GraphMatrixError::invalid_parameter("k must be in [1, n]"))
+    }
+    sample(rng, n, k).into_iter().map(from_usize).collect()
+}
+
+/// Runs `kernel` once for every source, summing what it adds into a per-vertex total.
+fn sum_over<F>(n: usize, sources: &[usize], kernel: F) -> Vec<f64>
+where
+    F: Fn(usize, &mut [f64]),
+{
+    let mut total = vec![0.0; n];
+    for &s in sources {
+        kernel(s, &mut total);
+    }
+    total
+}
+
+/// Like `sum_over`, but each rayon worker sums into its own total and the totals are then
+/// added together.
+#[cfg(feature = "rayon")]
+fn par_sum_over<F>(n: usize, sources: &[usize], kernel: F) -> Vec<f64>
+where
+    F: Fn(usize, &mut [f64]) + Sync,
+{
+    sources
+        .par_iter()
+        .fold(|| vec![0.0; n], |mut total, &s| {
+            kernel(s, &mut total);
+            total
+        })
+        .reduce(|| vec![0.0; n], |mut a, b| {
+            a.iter_mut().zip(&b).for_each(|(x, y)| *x += y);
+            a
+        })
+}
+
+/// Breadth-first search from `s`. Returns the distance to each vertex (`usize::MAX` if
+/// unreachable), the number of shortest paths to each vertex, and the vertices in the order
+/// they were reached.
+fn count_paths<T: PrimInt>(g: &GraphMatrix<T>, s: usize) -> (Vec<usize>, Vec<f64>, Vec<usize>) {
+    let n = g.indptr.len() - 1;
+    let mut dist = vec![usize::MAX; n];
+    let mut sigma = vec![0.0; n];
+    let mut order = Vec::with_capacity(n);
+    dist[s] = 0;
+    sigma[s] = 1.0;
+    order.push(s);
+    let mut head = 0;
+    while head < order.len() {
+        let v = order[head];
+        head += 1;
+        for &w in g.row_slice(v) {
+            let w = index(w);
+            if dist[w] == usize::MAX {
+                dist[w] = dist[v] + 1;
+                order.push(w);
+            }
+            if dist[w] == dist[v] + 1 {
+                sigma[w] += sigma[v];
+            }
+        }
+    }
+    (dist, sigma, order)
+}
+
+/// Adds the dependency of each vertex on `s` to `total`. Vertices are visited in reverse
+/// order of distance, and each collects its share from the successors that lie on a shortest
+/// path through it.
+fn dependencies<T: PrimInt>(g: &GraphMatrix<T>, s: usize, total: &mut [f64]) {
+    let (dist, sigma, order) = count_paths(g, s);
+    let mut delta = vec![0.0; total.len()];
+    for &v in order.iter().rev() {
+        for &w in g.row_slice(v) {
+            let w = index(w);
+            if dist[w] == dist[v] + 1 {
+                delta[v] += sigma[v] / sigma[w] * (1.0 + delta[w]);
+            }
+        }
+        if v != s {
+            total[v] += delta[v];
+        }
+    }
+}
+
+/// Like `dependencies`, but over weighted shortest paths found by Dijkstra's algorithm.
+fn weighted_dependencies<T, W>(g: &GraphMatrix<T>, weights: &[W], s: usize, total: &mut [f64])
+where
+    T: PrimInt,
+    W: Copy + PartialOrd + Zero,
+{
+    let n = total.len();
+    let mut dist: Vec<Option<W>> = vec![None; n];
+    let mut sigma = vec![0.0; n];
+    let mut order = Vec::with_capacity(n);
+    let mut settled = Bitset::new(n);
+    let mut heap = BinaryHeap::new();
+    dist[s] = Some(W::zero());
+    sigma[s] = 1.0;
+    heap.push(HeapEntry(W::zero(), s));
+
+    while let Some(HeapEntry(d, v)) = heap.pop() {
+        if !settled.insert(v) {
+            continue;
+        }
+        order.push(v);
+        for (w, &wt) in g.row_with_values(v, weights) {
+            let w = index(w);
+            let nd = d + wt;
+            if dist[w].is_none_or(|old| nd < old) {
+                dist[w] = Some(nd);
+                sigma[w] = sigma[v];
+                heap.push(HeapEntry(nd, w));
+            } else if dist[w] == Some(nd) {
+                sigma[w] += sigma[v];
+            }
+        }
+    }
+
+    let mut delta = vec![0.0; n];
+    for &v in order.iter().rev() {
+        let dv = dist[v].expect("settled vertices have a distance");
+        for (w, &wt) in g.row_with_values(v, weights) {
+            let w = index(w);
+            if dist[w] == Some(dv + wt) {
+                delta[v] += sigma[v] / sigma[w] * (1.0 + delta[w]);
+            }
+        }
+        if v != s {
+            total[v] += delta[v];
+        }
+    }
+}
+
+/// Checks that every weight is strictly positive: with zero-weight edges, Dijkstra's
+/// algorithm may settle a vertex before all of its shortest paths have been counted.
+fn check_positive<W: PartialOrd + Zero>(weights: &[W]) -> Result<(), GraphMatrixError> {
+    check_nonnegative(weights)?;
+    if weights.iter().any(|w| w.is_zero()) {
+        return Err(GraphMatrixError::invalid_parameter("weights must be positive"))
+    }
+    Ok(())
+}
+
+/// Computes Brandes' betweenness centrality. For each vertex, this is the sum over ordered
+/// pairs `(s, t)` of the fraction of shortest `s`-`t` paths that pass through it. Every
+/// edge has length one. Given `pivots`, which must be distinct (see `sample_pivots`), only
+/// those sources are searched, and the result is scaled by `n / pivots.len()` to estimate
+/// the exact scores. Runs in O(VE), or O(kE) with k pivots.
+pub fn betweenness<T: PrimInt>(
+    g: &GraphMatrix<T>,
+    pivots: Option<&[T]>,
+) -> Result<Vec<f64>, GraphMatrixError> {
+    let (sources, scale) = sources(g, pivots)?;
+    let total = sum_over(g.nv()?, &sources, |s, total| dependencies(g, s, total));
+    Ok(total.into_iter().map(|x| x * scale).collect())
+}
+
+/// Computes betweenness centrality with edge lengths taken from `weights`, which is aligned
+/// with `g`'s indices. Weights must be positive: a negative weight gives
+/// `GraphMatrixError::NegativeWeight`, and a zero weight gives `InvalidParameter`. Runs in
+/// O(VE log V).
+pub fn weighted_betweenness<T, W>(
+    g: &GraphMatrix<T>,
+    weights: &[W],
+    pivots: Option<&[T]>,
+) -> Result<Vec<f64>, GraphMatrixError>
+where
+    T: PrimInt,
+    W: Copy + PartialOrd + Zero,
+{
+    let n = check_weights(g, weights)?;
+    check_positive(weights)?;
+    let (sources, scale) = sources(g, pivots)?;
+    let total = sum_over(n, &sources, |s, total| weighted_dependencies(g, weights, s, total));
+    Ok(total.into_iter().map(|x| x * scale).collect())
+}
+
+/// Adds the closeness of `s` to `total[s]`. This is the number of vertices `r` that `s`
+/// reaches, divided by the sum of their distances and then scaled by `r / (n - 1)`. The
+/// scaling keeps vertices that reach only a few close vertices from scoring highest.
+fn closeness_of<T: PrimInt>(g: &GraphMatrix<T>, s: usize, total: &mut [f64]) {
+    let (dist, _, order) = count_paths(g, s);
+    let reached = (order.len() - 1) as f64;
+    let sum: usize = order.iter().map(|&v| dist[v]).sum();
+    if sum > 0 {
+        total[s] += reached / sum as f64 * reached / (total.len() - 1) as f64;
+    }
+}
+
+/// Adds the harmonic centrality of `s`, the sum of the reciprocal distances to every other
+/// vertex, to `total[s]`.
+fn harmonic_of<T: PrimInt>(g: &GraphMatrix<T>, s: usize, total: &mut [f64]) {
+    let (dist, _, order) = count_paths(g, s);
+    total[s] += order[1..].iter().map(|&v| 1.0 / dist[v] as f64).sum::<f64>();
+}
+
+/// Computes the closeness centrality of every vertex from distances along out-edges, with
+/// the Wasserman–Faust scaling for graphs that are not strongly connected. Vertices that
+/// reach nothing score zero. Runs in O(V(V + E)).
+pub fn closeness<T: PrimInt>(g: &GraphMatrix<T>) -> Result<Vec<f64>, GraphMatrixError> {
+    let n = g.nv()?;
+    let all: Vec<usize> = (0..n).collect();
+    Ok(sum_over(n, &all, |s, total| closeness_of(g, s, total)))
+}
+
+/// Computes the harmonic centrality of every vertex: the sum of the reciprocal distances to
+/// all others along out-edges, with unreachable vertices contributing zero. Runs in
+/// O(V(V + E)).
+pub fn harmonic<T: PrimInt>(g: &GraphMatrix<T>) -> Result<Vec<f64>, GraphMatrixError> {
+    let n = g.nv()?;
+    let all: Vec<usize> = (0..n).collect();
+    Ok(sum_over(n, &all, |s, total| harmonic_of(g, s, total)))
+}
+
+/// `betweenness`, with the sources searched in parallel.
+#[cfg(feature = "rayon")]
+pub fn par_betweenness<T: PrimInt + Sync>(
+    g: &GraphMatrix<T>,
+    pivots: Option<&[T]>,
+) -> Result<Vec<f64>, GraphMatrixError> {
+    let (sources, scale) = sources(g, pivots)?;
+    let total = par_sum_over(g.nv()?, &sources, |s, total| dependencies(g, s, total));
+    Ok(total.into_iter().map(|x| x * scale).collect())
+}
+
+/// `weighted_betweenness`, with the sources searched in parallel.
+#[cfg(feature = "rayon")]
+pub fn par_weighted_betweenness<T, W>(
+    g: &GraphMatrix<T>,
+    weights: &[W],
+    pivots: Option<&[T]>,
+) -> Result<Vec<f64>, GraphMatrixError>
+where
+    T: PrimInt + Sync,
+    W: Copy + PartialOrd + Zero + Sync,
+{
+    let n = check_weights(g, weights)?;
+    check_positive(weights)?;
+    let (sources, scale) = sources(g, pivots)?;
+    let total =
+        par_sum_over(n, &sources, |s, total| weighted_dependencies(g, weights, s, total));
+    Ok(total.into_iter().map(|x| x * scale).collect())
+}
+
+/// `closeness`, with the sources searched in parallel.
+#[cfg(feature = "rayon")]
+pub fn par_closeness<T: PrimInt + Sync>(g: &GraphMatrix<T>) -> Result<Vec<f64>, GraphMatrixError> {
+    let n = g.nv()?;
+    let all: Vec<usize> = (0..n).collect();
+    Ok(par_sum_over(n, &all, |s, total| closeness_of(g, s, total)))
+}
+
+/// `harmonic`, with the sources searched in parallel.
+#[cfg(feature = "rayon")]
+pub fn par_harmonic<T: PrimInt + Sync>(g: &GraphMatrix<T>) -> Result<Vec<f64>, GraphMatrixError> {
+    let n = g.nv()?;
+    let all: Vec<usize> = (0..n).collect();
+    Ok(par_sum_over(n, &all, |s, total| harmonic_of(g, s, total)))
+}
+
+#[cfg(test)]
+mod tests {
+    use rand::rngs::StdRng;
+    use rand::{Rng, SeedableRng};
+
+    use super::*;
+
+    /// The undirected path 0 - 1 - 2 - 3.
+    fn path() -> GraphMatrix<u32> {
+        let edges = vec![(0, 1), (1, 0), (1, 2), (2, 1), (2, 3), (3, 2)];
+        GraphMatrix::from_edgelist(edges).unwrap()
+    }
+
+    fn random_graph(n: u32, m: usize, seed: u64) -> GraphMatrix<u32> {
+        let mut rng = StdRng::seed_from_u64(seed);
+        let edges = (0..m).map(|_| (rng.gen_range(0..n), rng.gen_range(0..n))).collect();
+        GraphMatrix::from_edgelist_with_dims(edges, n as usize, n as usize).unwrap()
+    }
+
+    /// Distances and shortest-path counts between every pair, by breadth-first search.
+    fn all_pairs(g: &GraphMatrix<u32>) -> (Vec<Vec<Option<usize>>>, Vec<Vec<f64>>) {
+        let n = g.dims().0;
+        let (mut dist, mut sigma) = (vec![vec![None; n]; n], vec![vec![0.0; n]; n]);
+        for s in 0..n {
+            dist[s][s] = Some(0);
+            sigma[s][s] = 1.0;
+            let mut frontier = vec![s];
+            for d in 1.. {
+                let mut next = Vec::new();
+                for &u in &frontier {
+                    for &v in g.row_slice(u) {
+                        let v = v as usize;
+                        if dist[s][v].is_none() {
+                            dist[s][v] = Some(d);
+                            next.push(v);
+                        }
+                        if dist[s][v] == Some(d) {
+                            sigma[s][v] += sigma[s][u];
+                        }
+                    }
+                }
+                if next.is_empty() {
+                    break;
+                }
+                frontier = next;
+            }
+        }
+        (dist, sigma)
+    }
+
+    fn assert_close(a: &[f64], b: &[f64]) {
+        assert_eq!(a.len(), b.len());
+        for (x, y) in a.iter().zip(b) {
+            assert!((x - y).abs() < 1e-9, "{} != {}", x, y);
+        }
+    }
+
+    #[test]
+    fn betweenness_on_a_path() {
+        assert_eq!(betweenness(&path(), None).unwrap(), vec![0.0, 4.0, 4.0, 0.0]);
+    }
+
+    #[test]
+    fn betweenness_matches_path_counting() {
+        for seed in 0..4 {
+            let g = random_graph(40, 100, seed);
+            let (dist, sigma) = all_pairs(&g);
+            let mut expected = vec![0.0; 40];
+            for (s, t) in (0..40).flat_map(|s| (0..40).map(move |t| (s, t))) {
+                for v in (0..40).filter(|&v| s != t && v != s && v != t) {
+                    let (Some(sv), Some(vt), Some(st)) = (dist[s][v], dist[v][t], dist[s][t])
+                    else {
+                        continue;
+                    };
+                    if sv + vt == st {
+                        expected[v] += sigma[s][v] * sigma[v][t] / sigma[s][t];
+                    }
+                }
+            }
+            assert_close(&betweenness(&g, None).unwrap(), &expected);
+            let ones = vec![1u32; g.ne()];
+            assert_close(&weighted_betweenness(&g, &ones, None).unwrap(), &expected);
+        }
+    }
+
+    #[test]
+    fn weights_change_the_shortest_paths() {
+        // 0 -> 1 -> 2 is shorter than the direct edge 0 -> 2.
+        let g = GraphMatrix::<u32>::from_edgelist(vec![(0, 1), (0, 2), (1, 2)]).unwrap();
+        assert_eq!(betweenness(&g, None).unwrap(), vec![0.0; 3]);
+        let weights = [1.0, 5.0, 1.0];
+        assert_eq!(weighted_betweenness(&g, &weights, None).unwrap(), vec![0.0, 1.0, 0.0]);
+        let zero = weighted_betweenness(&g, &[1.0, 0.0, 1.0], None);
+        assert!(matches!(zero, Err(GraphMatrixError::InvalidParameter { .. })));
+        let negative = weighted_betweenness(&g, &[1.0, -1.0, 1.0], None);
+        assert!(matches!(negative, Err(GraphMatrixError::NegativeWeight { .. })));
+    }
+
+    #[test]
+    fn closeness_and_harmonic_on_a_path() {
+        assert_close(&closeness(&path()).unwrap(), &[0.5, 0.75, 0.75, 0.5]);
+        let h = [1.0 + 1.0 / 2.0 + 1.0 / 3.0, 2.5, 2.5, 1.0 + 1.0 / 2.0 + 1.0 / 3.0];
+        assert_close(&harmonic(&path()).unwrap(), &h);
+    }
+
+    #[test]
+    fn unreachable_vertices_score_zero() {
+        // 0 -> 1, with 2 isolated: 0 reaches one of the two others.
+        let g = GraphMatrix::<u32>::from_edgelist_with_dims(vec![(0, 1)], 3, 3).unwrap();
+        assert_eq!(closeness(&g).unwrap(), vec![0.5, 0.0, 0.0]);
+        assert_eq!(harmonic(&g).unwrap(), vec![1.0, 0.0, 0.0]);
+    }
+
+    #[cfg(feature = "rayon")]
+    #[test]
+    fn parallel_variants_agree() {
+        let g = random_graph(60, 200, 18);
+        let ones = vec![2.0; g.ne()];
+        assert_close(&par_betweenness(&g, None).unwrap(), &betweenness(&g, None).unwrap());
+        let weighted = weighted_betweenness(&g, &ones, None).unwrap();
+        assert_close(&par_weighted_betweenness(&g, &ones, None).unwrap(), &weighted);
+        assert_close(&par_closeness(&g).unwrap(), &closeness(&g).unwrap());
+        assert_close(&par_harmonic(&g).unwrap(), &harmonic(&g).unwrap());
+    }
+
+    #[test]
+    fn betweenness_rejects_repeated_pivots() {
+        let err = betweenness(&path(), Some(&[1, 2, 1])).unwrap_err();
+        assert_eq!(err, GraphMatrixError::invalid_parameter("pivots must be distinct"));
+    }
+
+    #[test]
+    fn sample_pivots_draws_distinct_vertices() {
+        let g = path();
+        let pivots = sample_pivots(&g, 3, &mut StdRng::seed_from_u64(1)).unwrap();
+        assert_eq!(pivots, sample_pivots(&g, 3, &mut StdRng::seed_from_u64(1)).unwrap());
+        let mut sorted = pivots.clone();
+        sorted.sort_unstable();
+        sorted.dedup();
+        assert_eq!(sorted.len(), 3);
+        assert!(betweenness(&g, Some(&pivots)).is_ok());
+
+        let all = sample_pivots(&g, 4, &mut StdRng::seed_from_u64(2)).unwrap();
+        assert_eq!(betweenness(&g, Some(&all)).unwrap(), betweenness(&g, None).unwrap());
+        let err = GraphMatrixError::invalid_parameter("k must be in [1, n]");
+        assert_eq!(sample_pivots(&g, 5, &mut StdRng::seed_from_u64(3)).unwrap_err(), err);
+    }
+}
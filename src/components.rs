@@ -0,0 +1,554 @@
+//! Connected and strongly connected components of a square GraphMatrix.
+//!
+//! Component labels are vectors with one entry per vertex. Every function here labels a
+//! component by its smallest vertex, so the different algorithms agree exactly.
+
+use std::collections::HashMap;
+#[cfg(feature = "rayon")]
+use std::sync::atomic::{AtomicUsize, Ordering::Relaxed};
+
+use num::PrimInt;
+#[cfg(feature = "rayon")]
+use rayon::prelude::*;
+
+#[cfg(feature = "rayon")]
+use crate::index;
+use crate::{from_usize, to_usize, BiGraphMatrix, Bitset, GraphMatrix, GraphMatrixError};
+
+/// A disjoint-set forest whose roots are always the smallest member of their set.
+struct UnionFind {
+    parent: Vec<usize>,
+}
+
+impl UnionFind {
+    fn new(n: usize) -> Self {
+        UnionFind {parent: (0..n).collect()}
+    }
+
+    fn find(&mut self, mut x: usize) -> usize {
+        // path halving
+        while self.parent[x] != x {
+            self.parent[x] = self.parent[self.parent[x]];
+            x = self.parent[x];
+        }
+        x
+    }
+
+    fn union(&mut self, a: usize, b: usize) {
+        let (ra, rb) = (self.find(a), self.find(b));
+        if ra < rb {
+            self.parent[rb] = ra;
+        } else if rb < ra {
+            self.parent[ra] = rb;
+        }
+    }
+}
+
+fn to_labels<T: PrimInt>(comp: &[usize]) -> Result<Vec<T>, GraphMatrixError> {
+    comp.iter().map(|&c| from_usize(c)).collect()
+}
+
+/// Labels the weakly connected components of `g` (edge direction is ignored) using a
+/// union-find over every edge. Runs in O(E α(V)).
+pub fn weakly_connected<T: PrimInt>(g: &GraphMatrix<T>) -> Result<Vec<T>, GraphMatrixError> {
+    let n = g.nv()?;
+    let mut uf = UnionFind::new(n);
+    for (u, w) in g.indptr.windows(2).enumerate() {
+        for &v in &g.indices[w[0]..w[1]] {
+            uf.union(u, to_usize(v)?);
+        }
+    }
+    let comp: Vec<usize> = (0..n).map(|v| uf.find(v)).collect();
+    to_labels(&comp)
+}
+
+// Parameters from Sutton, Ben-Nun and Barak, "Optimizing Parallel Graph Connectivity
+// Computation via Subgraph Sampling" (IPDPS '18).
+const NEIGHBOR_ROUNDS: usize = 2;
+const NUM_SAMPLES: usize = 1024;
+
+/// Hooks the trees holding `u` and `v` together, always pointing the larger root at the
+/// smaller one.
+fn link(comp: &mut [usize], u: usize, v: usize) {
+    let (mut p1, mut p2) = (comp[u], comp[v]);
+    while p1 != p2 {
+        let (high, low) = if p1 > p2 { (p1, p2) } else { (p2, p1) };
+        let p_high = comp[high];
+        if p_high == low {
+            break;
+        }
+        if p_high == high {
+            comp[high] = low;
+            break;
+        }
+        p1 = comp[comp[high]];
+        p2 = comp[low];
+    }
+}
+
+fn compress(comp: &mut [usize]) {
+    for v in 0..comp.len() {
+        while comp[v] != comp[comp[v]] {
+            comp[v] = comp[comp[v]];
+        }
+    }
+}
+
+/// Labels the weakly connected components of `g` with Afforest, a refinement of
+/// Shiloach-Vishkin. It first links along a couple of edges per vertex, which usually
+/// merges most of the graph into one giant component, then finds that component by
+/// sampling and skips linking edges inside it.
+///
+/// If `g` is symmetric (an undirected graph stored in both directions), pass
+/// `symmetric = true` and the giant component's edges are not even scanned. Otherwise they
+/// are scanned, but only edges leaving the component are linked.
+///
+/// This runs on one thread; with the `rayon` feature, `par_afforest` spreads the linking
+/// across the thread pool.
+pub fn afforest<T: PrimInt>(
+    g: &GraphMatrix<T>,
+    symmetric: bool,
+) -> Result<Vec<T>, GraphMatrixError> {
+    let n = g.nv()?;
+    let mut comp: Vec<usize> = (0..n).collect();
+
+    for r in 0..NEIGHBOR_ROUNDS {
+        for u in 0..n {
+            if let Some(&v) = g.row_slice(u).get(r) {
+                link(&mut comp, u, to_usize(v)?);
+            }
+        }
+        compress(&mut comp);
+    }
+
+    let giant = sample_frequent(&comp);
+    for u in 0..n {
+        let in_giant = comp[u] == giant;
+        if in_giant && symmetric {
+            continue;
+        }
+        for &v in g.row_slice(u).iter().skip(NEIGHBOR_ROUNDS) {
+            let vu = to_usize(v)?;
+            if !in_giant || comp[vu] != giant {
+                link(&mut comp, u, vu);
+            }
+        }
+    }
+    compress(&mut comp);
+    to_labels(&comp)
+}
+
+/// `link`, for trees shared between threads. A root is only re-pointed if it is still a
+/// root, so concurrent links never lose a merge.
+#[cfg(feature = "rayon")]
+fn par_link(comp: &[AtomicUsize], u: usize, v: usize) {
+    let (mut p1, mut p2) = (comp[u].load(Relaxed), comp[v].load(Relaxed));
+    while p1 != p2 {
+        let (high, low) = if p1 > p2 { (p1, p2) } else { (p2, p1) };
+        let p_high = comp[high].load(Relaxed);
+        if p_high == low {
+            break;
+        }
+        if p_high == high && comp[high].compare_exchange(high, low, Relaxed, Relaxed).is_ok() {
+            break;
+        }
+        p1 = comp[comp[high].load(Relaxed)].load(Relaxed);
+        p2 = comp[low].load(Relaxed);
+    }
+}
+
+/// `compress`, for trees shared between threads. Parents only ever decrease, so shortcuts
+/// taken concurrently stay inside the same tree.
+#[cfg(feature = "rayon")]
+fn par_compress(comp: &[AtomicUsize]) {
+    (0..comp.len()).into_par_iter().for_each(|v| loop {
+        let p = comp[v].load(Relaxed);
+        let gp = comp[p].load(Relaxed);
+        if p == gp {
+            break;
+        }
+        comp[v].store(gp, Relaxed);
+    });
+}
+
+/// `afforest`, with the vertices linked in parallel. The labels are the same.
+#[cfg(feature = "rayon")]
+pub fn par_afforest<T: PrimInt + Sync>(
+    g: &GraphMatrix<T>,
+    symmetric: bool,
+) -> Result<Vec<T>, GraphMatrixError> {
+    let n = g.nv()?;
+    let comp: Vec<AtomicUsize> = (0..n).map(AtomicUsize::new).collect();
+
+    for r in 0..NEIGHBOR_ROUNDS {
+        (0..n).into_par_iter().for_each(|u| {
+            if let Some(&v) = g.row_slice(u).get(r) {
+                par_link(&comp, u, index(v));
+            }
+        });
+        par_compress(&comp);
+    }
+
+    let snapshot: Vec<usize> = comp.iter().map(|c| c.load(Relaxed)).collect();
+    let giant = sample_frequent(&snapshot);
+    (0..n).into_par_iter().for_each(|u| {
+        let in_giant = comp[u].load(Relaxed) == giant;
+        if in_giant && symmetric {
+            return;
+        }
+        for &v in g.row_slice(u).iter().skip(NEIGHBOR_ROUNDS) {
+            let vu = index(v);
+            if !in_giant || comp[vu].load(Relaxed) != giant {
+                par_link(&comp, u, vu);
+            }
+        }
+    });
+    par_compress(&comp);
+    let comp: Vec<usize> = comp.into_iter().map(AtomicUsize::into_inner).collect();
+    to_labels(&comp)
+}
+
+/// Returns the most common label among evenly spaced samples of `comp`.
+fn sample_frequent(comp: &[usize]) -> usize {
+    let n = comp.len();
+    let samples = NUM_SAMPLES.min(n);
+    let mut counts: HashMap<usize, usize> = HashMap::new();
+    for i in 0..samples {
+        *counts.entry(comp[i * n / samples]).or_default() += 1;
+    }
+    counts
+        .into_iter()
+        .max_by_key(|&(c, k)| (k, std::cmp::Reverse(c)))
+        .map_or(0, |(c, _)| c)
+}
+
+/// Returns the size of every component, indexed by label. Entries for values that are not
+/// labels are zero.
+pub fn component_sizes<T: PrimInt>(labels: &[T]) -> Result<Vec<usize>, GraphMatrixError> {
+    let n = labels.len();
+    let mut sizes = vec![0; n];
+    for &l in labels {
+        let lu = to_usize(l)?;
+        if lu >= n {
+            return Err(GraphMatrixError::BoundsError {index: lu, bound: n, edge: None})
+        }
+        sizes[lu] += 1;
+    }
+    Ok(sizes)
+}
+
+/// Returns a mask of the vertices in the largest component, breaking ties by the smaller
+/// label.
+pub fn largest_component_mask<T: PrimInt>(labels: &[T]) -> Result<Bitset, GraphMatrixError> {
+    let sizes = component_sizes(labels)?;
+    let mut mask = Bitset::new(labels.len());
+    let largest = sizes
+        .iter()
+        .enumerate()
+        .max_by_key(|&(l, &s)| (s, std::cmp::Reverse(l)))
+        .map(|(l, _)| l);
+    for (v, &l) in labels.iter().enumerate() {
+        if Some(to_usize(l)?) == largest {
+            mask.insert(v);
+        }
+    }
+    Ok(mask)
+}
+
+/// Extracts the component labelled `label` as its own GraphMatrix, together with the
+/// original id of each of its vertices.
+pub fn extract_component<T: PrimInt>(
+    g: &GraphMatrix<T>,
+    labels: &[T],
+    label: T,
+) -> Result<(GraphMatrix<T>, Vec<T>), GraphMatrixError> {
+    let keep = Bitset::from_indices(
+        labels.len(),
+        labels.iter().enumerate().filter(|&(_, &l)| l == label).map(|(v, _)| v),
+    );
+    g.induced_subgraph(&keep)
+}
+
+/// Labels the strongly connected components of `g` with Tarjan's algorithm, using an
+/// explicit stack so long paths cannot overflow the call stack. Runs in O(V + E).
+pub fn strongly_connected<T: PrimInt>(g: &GraphMatrix<T>) -> Result<Vec<T>, GraphMatrixError> {
+    const UNVISITED: usize = usize::MAX;
+    let n = g.nv()?;
+    let mut index = vec![UNVISITED; n];
+    let mut lowlink = vec![0; n];
+    let mut on_stack = Bitset::new(n);
+    let mut scc_stack: Vec<usize> = Vec::new();
+    // (vertex, position of its next unexplored edge)
+    let mut call_stack: Vec<(usize, usize)> = Vec::new();
+    let mut comp = vec![0; n];
+    let mut next_index = 0;
+
+    for root in 0..n {
+        if index[root] != UNVISITED {
+            continue;
+        }
+        index[root] = next_index;
+        lowlink[root] = next_index;
+        next_index += 1;
+        scc_stack.push(root);
+        on_stack.insert(root);
+        call_stack.push((root, g.indptr[root]));
+
+        while let Some(&mut (v, ref mut pos)) = call_stack.last_mut() {
+            if *pos < g.indptr[v + 1] {
+                let w = to_usize(g.indices[*pos])?;
+                *pos += 1;
+                if index[w] == UNVISITED {
+                    index[w] = next_index;
+                    lowlink[w] = next_index;
+                    next_index += 1;
+                    scc_stack.push(w);
+                    on_stack.insert(w);
+                    call_stack.push((w, g.indptr[w]));
+                } else if on_stack.contains(w) {
+                    lowlink[v] = lowlink[v].min(index[w]);
+                }
+                continue;
+            }
+            call_stack.pop();
+            if let Some(&(parent, _)) = call_stack.last() {
+                lowlink[parent] = lowlink[parent].min(lowlink[v]);
+            }
+            if lowlink[v] == index[v] {
+                let start = scc_stack.iter().rposition(|&x| x == v).expect("v is on the stack");
+                let members = scc_stack.split_off(start);
+                let label = *members.iter().min().expect("v is a member");
+                for &x in &members {
+                    on_stack.remove(x);
+                    comp[x] = label;
+                }
+            }
+        }
+    }
+    to_labels(&comp)
+}
+
+/// Labels the strongly connected components of `g` by forward-backward decomposition: the
+/// vertices both reachable from and reaching a pivot form its component, and the vertices
+/// reached in only one direction (or neither) are split off and processed independently.
+/// Needs in-edges, hence the BiGraphMatrix. Each split is a pair of independent searches,
+/// which suits graphs with a few large components.
+pub fn strongly_connected_fw_bw<T: PrimInt>(
+    g: &BiGraphMatrix<T>,
+) -> Result<Vec<T>, GraphMatrixError> {
+    let (fwd, bwd) = (g.forward(), g.backward());
+    let n = fwd.nv()?;
+    // every vertex belongs to a partition; searches never leave the pivot's partition.
+    let mut part = vec![0usize; n];
+    let mut next_part = 1;
+    let mut comp = vec![usize::MAX; n];
+    let mut work: Vec<Vec<usize>> = vec![(0..n).collect()];
+
+    while let Some(vertices) = work.pop() {
+        let Some(&pivot) = vertices.first() else { continue };
+        let fw = reach_within(fwd, &part, pivot)?;
+        let bw = reach_within(bwd, &part, pivot)?;
+        let (mut fw_only, mut bw_only, mut rest) = (Vec::new(), Vec::new(), Vec::new());
+        let mut members = Vec::new();
+        for &v in &vertices {
+            match (fw.contains(v), bw.contains(v)) {
+                (true, true) => members.push(v),
+                (true, false) => fw_only.push(v),
+                (false, true) => bw_only.push(v),
+                (false, false) => rest.push(v),
+            }
+        }
+        let label = *members.iter().min().expect("the pivot is a member");
+        for &v in &members {
+            comp[v] = label;
+        }
+        for split in [fw_only, bw_only, rest] {
+            if split.is_empty() {
+                continue;
+            }
+            for &v in &split {
+                part[v] = next_part;
+            }
+            next_part += 1;
+            work.push(split);
+        }
+    }
+    to_labels(&comp)
+}
+
+/// Returns the vertices reachable from `pivot` in `m` without leaving its partition.
+fn reach_within<T: PrimInt>(
+    m: &GraphMatrix<T>,
+    part: &[usize],
+    pivot: usize,
+) -> Result<Bitset, GraphMatrixError> {
+    let mut seen = Bitset::new(part.len());
+    seen.insert(pivot);
+    let mut stack = vec![pivot];
+    while let Some(u) = stack.pop() {
+        for &v in m.row_slice(u) {
+            let vu = to_usize(v)?;
+            if part[vu] == part[pivot] && seen.insert(vu) {
+                stack.push(vu);
+            }
+        }
+    }
+    Ok(seen)
+}
+
+/// Builds the condensation of `g` under `labels`: one vertex per distinct label, in
+/// increasing label order, with an edge between two of them whenever `g` has an edge
+/// between their members. Edges within a component are dropped. For strongly connected
+/// labels the result is a DAG. Also returns the condensed vertex of each original vertex.
+pub fn condensation<T: PrimInt>(
+    g: &GraphMatrix<T>,
+    labels: &[T],
+) -> Result<(GraphMatrix<T>, Vec<T>), GraphMatrixError> {
+    let n = g.nv()?;
+    if labels.len() != n {
+        return Err(GraphMatrixError::DimensionMismatch {expected: n, found: labels.len()})
+    }
+    let sizes = component_sizes(labels)?;
+    // compact id of each label, assigned in increasing label order.
+    let mut compact = vec![0usize; n];
+    let mut k = 0;
+    for (l, &s) in sizes.iter().enumerate() {
+        if s > 0 {
+            compact[l] = k;
+            k += 1;
+        }
+    }
+    let membership: Vec<usize> = labels
+        .iter()
+        .map(|&l| to_usize(l).map(|lu| compact[lu]))
+        .collect::<Result<_, _>>()?;
+
+    let mut edges: Vec<(T, T)> = Vec::new();
+    for (u, w) in g.indptr.windows(2).enumerate() {
+        for &v in &g.indices[w[0]..w[1]] {
+            let (cu, cv) = (membership[u], membership[to_usize(v)?]);
+            if cu != cv {
+                edges.push((from_usize(cu)?, from_usize(cv)?));
+            }
+        }
+    }
+    let dag = GraphMatrix::from_edgelist_with_dims(edges, k, k)?;
+    Ok((dag, to_labels(&membership)?))
+}
+
+#[cfg(test)]
+mod tests {
+    use rand::rngs::StdRng;
+    use rand::{Rng, SeedableRng};
+
+    use super::*;
+
+    /// A random digraph on `n` vertices with `m` edges drawn with replacement, and its
+    /// symmetric closure.
+    fn random_pair(n: u32, m: usize, seed: u64) -> (GraphMatrix<u32>, GraphMatrix<u32>) {
+        let mut rng = StdRng::seed_from_u64(seed);
+        let edges: Vec<(u32, u32)> =
+            (0..m).map(|_| (rng.gen_range(0..n), rng.gen_range(0..n))).collect();
+        let both = edges.iter().flat_map(|&(u, v)| [(u, v), (v, u)]).collect();
+        let directed = GraphMatrix::from_edgelist_with_dims(edges, n as usize, n as usize);
+        let symmetric = GraphMatrix::from_edgelist_with_dims(both, n as usize, n as usize);
+        (directed.unwrap(), symmetric.unwrap())
+    }
+
+    #[test]
+    fn labels_are_smallest_members() {
+        let g = GraphMatrix::<u32>::from_edgelist(vec![(0, 4), (2, 1), (3, 2), (5, 5), (6, 3)])
+            .unwrap();
+        let expected = vec![0, 1, 1, 1, 0, 5, 1];
+        assert_eq!(weakly_connected(&g).unwrap(), expected);
+        assert_eq!(afforest(&g, false).unwrap(), expected);
+        assert_eq!(component_sizes(&expected).unwrap(), vec![2, 4, 0, 0, 0, 1, 0]);
+        let mask = largest_component_mask(&expected).unwrap();
+        assert_eq!(mask.iter().collect::<Vec<_>>(), vec![1, 2, 3, 6]);
+        let (sub, ids) = extract_component(&g, &expected, 1).unwrap();
+        assert_eq!(ids, vec![1, 2, 3, 6]);
+        assert_eq!(sub.iter().collect::<Vec<_>>(), vec![(1, 0), (2, 1), (3, 2)]);
+        let err = component_sizes(&[0u32, 3, 1]).unwrap_err();
+        assert_eq!(err, GraphMatrixError::BoundsError {index: 3, bound: 3, edge: None});
+    }
+
+    #[test]
+    fn afforest_matches_union_find() {
+        for (seed, m) in [(1, 500), (2, 2000), (3, 8000)] {
+            let (directed, symmetric) = random_pair(2000, m, seed);
+            let expected = weakly_connected(&directed).unwrap();
+            assert_eq!(weakly_connected(&symmetric).unwrap(), expected);
+            assert_eq!(afforest(&directed, false).unwrap(), expected);
+            assert_eq!(afforest(&symmetric, true).unwrap(), expected);
+        }
+    }
+
+    #[cfg(feature = "rayon")]
+    #[test]
+    fn par_afforest_matches_union_find() {
+        for (seed, m) in [(1, 500), (2, 2000), (3, 8000)] {
+            let (directed, symmetric) = random_pair(2000, m, seed);
+            let expected = weakly_connected(&directed).unwrap();
+            assert_eq!(par_afforest(&directed, false).unwrap(), expected);
+            assert_eq!(par_afforest(&symmetric, true).unwrap(), expected);
+        }
+    }
+
+    /// Labels vertices by mutual reachability, straight from the definition.
+    fn brute_force_scc(g: &GraphMatrix<u32>) -> Vec<u32> {
+        let n = g.dims().0;
+        let reach: Vec<Vec<Option<usize>>> =
+            (0..n as u32).map(|v| crate::traversal::bfs(g, v).unwrap().distances).collect();
+        (0..n)
+            .map(|v| {
+                let same = |u: &usize| reach[v][*u].is_some() && reach[*u][v].is_some();
+                (0..n).find(same).unwrap() as u32
+            })
+            .collect()
+    }
+
+    #[test]
+    fn scc_algorithms_agree() {
+        for (seed, m) in [(1, 100), (2, 200), (3, 400)] {
+            let (g, _) = random_pair(150, m, seed);
+            let expected = brute_force_scc(&g);
+            assert_eq!(strongly_connected(&g).unwrap(), expected);
+            let (g, _) = random_pair(150, m, seed);
+            let bi = BiGraphMatrix::new(g).unwrap();
+            assert_eq!(strongly_connected_fw_bw(&bi).unwrap(), expected);
+        }
+    }
+
+    #[test]
+    fn condensation_is_acyclic() {
+        use crate::traversal::{DfsEvent, DfsEvents};
+        for seed in 4..8 {
+            let (g, _) = random_pair(150, 250, seed);
+            let labels = strongly_connected(&g).unwrap();
+            let (dag, ids) = condensation(&g, &labels).unwrap();
+            let k = labels.iter().enumerate().filter(|&(v, &l)| l as usize == v).count();
+            assert_eq!(dag.dims(), (k, k));
+            let mut events = DfsEvents::all(&dag).unwrap();
+            assert!(!events.any(|e| matches!(e, DfsEvent::BackEdge(..))));
+            for (u, v) in g.iter() {
+                let (cu, cv) = (ids[u as usize], ids[v as usize]);
+                assert_eq!(cu == cv, labels[u as usize] == labels[v as usize]);
+                assert!(cu == cv || dag.has_index(cu, cv).unwrap());
+            }
+        }
+    }
+
+    #[test]
+    fn scc_handles_long_chains() {
+        let n: u32 = 1_000_000;
+        let mut edges: Vec<(u32, u32)> = (0..n - 1).map(|v| (v, v + 1)).collect();
+        let g = GraphMatrix::from_edgelist(edges.clone()).unwrap();
+        let labels = strongly_connected(&g).unwrap();
+        assert!(labels.iter().enumerate().all(|(v, &l)| l as usize == v));
+        edges.push((n - 1, 0));
+        let g = GraphMatrix::from_edgelist(edges).unwrap();
+        assert!(strongly_connected(&g).unwrap().iter().all(|&l| l == 0));
+        let bi = BiGraphMatrix::new(g).unwrap();
+        assert!(strongly_connected_fw_bw(&bi).unwrap().iter().all(|&l| l == 0));
+    }
+}
@@ -0,0 +1,176 @@
+//! k-core decomposition of an undirected graph, stored as a symmetric GraphMatrix. The k-core
+//! is the largest subgraph in which every vertex has at least k neighbors; a vertex's core
+//! number is the largest k whose k-core contains it. Self-loops are ignored.
+
+use num::PrimInt;
+
+use crate::{from_usize, to_usize, Bitset, GraphMatrix, GraphMatrixError};
+
+/// The result of a core decomposition.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct CoreDecomposition<T> {
+    /// The core number of each vertex.
+    pub core: Vec<usize>,
+    /// The largest core number, or zero for an empty graph.
+    pub degeneracy: usize,
+    /// The vertices in the order they were peeled. Each has at most `degeneracy` neighbors
+    /// later in the order.
+    pub order: Vec<T>,
+}
+
+/// The Batagelj–Zaversnik algorithm: vertices are kept bucket-sorted by remaining degree, and
+/// repeatedly the one of least degree is removed, decrementing its higher-degree neighbors.
+/// Runs in O(V + E).
+pub fn core_decomposition<T: PrimInt>(
+    g: &GraphMatrix<T>,
+) -> Result<CoreDecomposition<T>, GraphMatrixError> {
+    let mut degree = g.degrees_without_loops()?;
+    let n = degree.len();
+    let max_degree = degree.iter().copied().max().unwrap_or(0);
+
+    // vert holds the vertices sorted by degree; bin[d] is where degree d starts in it and
+    // pos[v] is where v sits.
+    let mut bin = vec![0usize; max_degree + 1];
+    for &d in &degree {
+        bin[d] += 1;
+    }
+    let mut start = 0;
+    for b in bin.iter_mut() {
+        let count = *b;
+        *b = start;
+        start += count;
+    }
+    let mut pos = vec![0usize; n];
+    let mut vert = vec![0usize; n];
+    for (v, &d) in degree.iter().enumerate() {
+        pos[v] = bin[d];
+        vert[pos[v]] = v;
+        bin[d] += 1;
+    }
+    for d in (1..=max_degree).rev() {
+        bin[d] = bin[d - 1];
+    }
+    bin[0] = 0;
+
+    for i in 0..n {
+        let v = vert[i];
+        for &u in g.row_slice(v) {
+            let u = to_usize(u)?;
+            if degree[u] > degree[v] {
+                // move u to the front of its bin, then shrink the bin past it.
+                let du = degree[u];
+                let w = vert[bin[du]];
+                if u != w {
+                    vert.swap(pos[u], bin[du]);
+                    pos[w] = pos[u];
+                    pos[u] = bin[du];
+                }
+                bin[du] += 1;
+                degree[u] -= 1;
+            }
+        }
+    }
+    let degeneracy = degree.iter().copied().max().unwrap_or(0);
+    let order = vert.into_iter().map(from_usize).collect::<Result<_, _>>()?;
+    Ok(CoreDecomposition {core: degree, degeneracy, order})
+}
+
+/// Extracts the k-core of `g` as its own GraphMatrix, together with the original id of each
+/// of its vertices. The result is empty if `k` exceeds the degeneracy.
+pub fn k_core<T: PrimInt>(
+    g: &GraphMatrix<T>,
+    k: usize,
+) -> Result<(GraphMatrix<T>, Vec<T>), GraphMatrixError> {
+    let core = core_decomposition(g)?.core;
+    let keep = Bitset::from_indices(core.len(), (0..core.len()).filter(|&v| core[v] >= k));
+    g.induced_subgraph(&keep)
+}
+
+#[cfg(test)]
+mod tests {
+    use rand::rngs::StdRng;
+    use rand::{Rng, SeedableRng};
+
+    use super::*;
+
+    fn undirected(n: usize, edges: &[(u32, u32)]) -> GraphMatrix<u32> {
+        let both = edges.iter().flat_map(|&(u, v)| [(u, v), (v, u)]).collect();
+        GraphMatrix::from_edgelist_with_dims(both, n, n).unwrap()
+    }
+
+    fn random_graph(n: u32, m: usize, seed: u64) -> GraphMatrix<u32> {
+        let mut rng = StdRng::seed_from_u64(seed);
+        let edges: Vec<_> = (0..m).map(|_| (rng.gen_range(0..n), rng.gen_range(0..n))).collect();
+        undirected(n as usize, &edges)
+    }
+
+    fn degrees(g: &GraphMatrix<u32>) -> Vec<usize> {
+        let n = g.dims().0;
+        (0..n).map(|v| g.row_slice(v).iter().filter(|&&u| u as usize != v).count()).collect()
+    }
+
+    /// The vertices left after repeatedly deleting those with fewer than `k` neighbors.
+    fn peel(g: &GraphMatrix<u32>, k: usize) -> Vec<bool> {
+        let n = g.dims().0;
+        let mut alive = vec![true; n];
+        loop {
+            let dropped: Vec<usize> = (0..n)
+                .filter(|&v| alive[v])
+                .filter(|&v| {
+                    let row = g.row_slice(v).iter().map(|&u| u as usize);
+                    row.filter(|&u| u != v && alive[u]).count() < k
+                })
+                .collect();
+            if dropped.is_empty() {
+                return alive;
+            }
+            for v in dropped {
+                alive[v] = false;
+            }
+        }
+    }
+
+    #[test]
+    fn clique_with_a_pendant() {
+        let mut edges: Vec<_> = (0..5).flat_map(|u| (u + 1..5).map(move |v| (u, v))).collect();
+        edges.extend([(0, 5), (2, 2)]);
+        let g = undirected(6, &edges);
+        let cd = core_decomposition(&g).unwrap();
+        assert_eq!(cd.core, vec![4, 4, 4, 4, 4, 1]);
+        assert_eq!(cd.degeneracy, 4);
+        assert_eq!(cd.order[0], 5);
+        let (core, ids) = k_core(&g, 4).unwrap();
+        assert_eq!(ids, vec![0, 1, 2, 3, 4]);
+        assert_eq!(degrees(&core), vec![4; 5]);
+        assert!(k_core(&g, 5).unwrap().1.is_empty());
+    }
+
+    #[test]
+    fn cores_match_peeling() {
+        for seed in 0..4 {
+            let g = random_graph(300, 1200, seed);
+            let cd = core_decomposition(&g).unwrap();
+            for k in 0..=cd.degeneracy + 1 {
+                let alive = peel(&g, k);
+                let expected: Vec<u32> = (0..300).filter(|&v| alive[v as usize]).collect();
+                let (core, ids) = k_core(&g, k).unwrap();
+                assert_eq!(ids, expected);
+                assert!(degrees(&core).iter().all(|&d| d >= k));
+            }
+        }
+    }
+
+    #[test]
+    fn order_bounds_later_neighbors() {
+        let g = random_graph(300, 1200, 17);
+        let cd = core_decomposition(&g).unwrap();
+        let mut rank = vec![0; 300];
+        for (i, &v) in cd.order.iter().enumerate() {
+            rank[v as usize] = i;
+        }
+        for v in 0..300 {
+            let later = g.row_slice(v).iter().filter(|&&u| rank[u as usize] > rank[v]).count();
+            assert!(later <= cd.degeneracy);
+        }
+    }
+}
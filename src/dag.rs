@@ -0,0 +1,223 @@
+//! Topological sorting and other utilities for directed acyclic graphs. Every function that
+//! needs acyclicity returns `GraphMatrixError::Cycle`, carrying a cycle as a witness, when
+//! the graph has one.
+
+use num::{PrimInt, Zero};
+
+use crate::traversal::{DfsEvent, DfsEvents};
+use crate::{from_usize, to_usize, GraphMatrix, GraphMatrixError};
+
+/// Kahn's algorithm: repeatedly removes vertices with no remaining in-edges. Returns the
+/// removal order, which covers every vertex exactly when `g` is acyclic.
+fn kahn<T: PrimInt>(g: &GraphMatrix<T>) -> Result<Vec<usize>, GraphMatrixError> {
+    let n = g.nv()?;
+    let mut indegree = vec![0usize; n];
+    for &v in &g.indices {
+        indegree[to_usize(v)?] += 1;
+    }
+    let mut order: Vec<usize> = (0..n).filter(|&v| indegree[v] == 0).collect();
+    let mut head = 0;
+    while head < order.len() {
+        let u = order[head];
+        head += 1;
+        for &v in g.row_slice(u) {
+            let vu = to_usize(v)?;
+            indegree[vu] -= 1;
+            if indegree[vu] == 0 {
+                order.push(vu);
+            }
+        }
+    }
+    Ok(order)
+}
+
+/// Finds a cycle with a depth-first search: the first back edge `u -> v` closes the cycle
+/// running from `v` down the DFS tree to `u`.
+fn find_cycle<T: PrimInt>(g: &GraphMatrix<T>) -> Result<Option<Vec<usize>>, GraphMatrixError> {
+    let mut parent = vec![usize::MAX; g.nv()?];
+    for e in DfsEvents::all(g)? {
+        match e {
+            DfsEvent::TreeEdge(u, v) => parent[to_usize(v)?] = to_usize(u)?,
+            DfsEvent::BackEdge(u, v) => {
+                let (u, v) = (to_usize(u)?, to_usize(v)?);
+                let mut cycle = vec![u];
+                let mut x = u;
+                while x != v {
+                    x = parent[x];
+                    cycle.push(x);
+                }
+                cycle.reverse();
+                return Ok(Some(cycle));
+            }
+            _ => {}
+        }
+    }
+    Ok(None)
+}
+
+/// Returns a topological order of `g`'s vertices (every edge points forward in it), or
+/// `GraphMatrixError::Cycle` if there is none. Runs in O(V + E).
+pub fn topological_sort<T: PrimInt>(g: &GraphMatrix<T>) -> Result<Vec<T>, GraphMatrixError> {
+    let order = kahn(g)?;
+    if order.len() < g.nv()? {
+        let cycle = find_cycle(g)?.expect("Kahn's algorithm stalls only on a cycle");
+        return Err(GraphMatrixError::Cycle {cycle})
+    }
+    order.into_iter().map(from_usize).collect()
+}
+
+/// Returns whether `g` has no directed cycles.
+pub fn is_dag<T: PrimInt>(g: &GraphMatrix<T>) -> Result<bool, GraphMatrixError> {
+    Ok(kahn(g)?.len() == g.nv()?)
+}
+
+/// Assigns every vertex a level: zero for vertices with no in-edges, and otherwise one more
+/// than the highest level among its in-neighbors (the length of the longest path ending at
+/// it). Every edge points to a strictly higher level.
+pub fn levels<T: PrimInt>(g: &GraphMatrix<T>) -> Result<Vec<usize>, GraphMatrixError> {
+    let order = topological_sort(g)?;
+    let mut level = vec![0usize; order.len()];
+    for u in order {
+        let u = to_usize(u)?;
+        for &v in g.row_slice(u) {
+            let vu = to_usize(v)?;
+            level[vu] = level[vu].max(level[u] + 1);
+        }
+    }
+    Ok(level)
+}
+
+/// Returns a path with the most edges in `g`.
+pub fn longest_path<T: PrimInt>(g: &GraphMatrix<T>) -> Result<Vec<T>, GraphMatrixError> {
+    let ones = vec![1usize; g.ne()];
+    Ok(longest_weighted_path(g, &ones)?.1)
+}
+
+/// Returns the total weight and vertices of a path of greatest total weight in `g`, where
+/// `weights` is aligned with `g`'s indices. Paths may start at any vertex; on an empty graph
+/// the path is empty.
+pub fn longest_weighted_path<T, W>(
+    g: &GraphMatrix<T>,
+    weights: &[W],
+) -> Result<(W, Vec<T>), GraphMatrixError>
+where
+    T: PrimInt,
+    W: Copy + PartialOrd + Zero,
+{
+    if weights.len() != g.ne() {
+        return Err(GraphMatrixError::DimensionMismatch {expected: g.ne(), found: weights.len()})
+    }
+    let order = topological_sort(g)?;
+    let n = order.len();
+    // best[v] is the weight of the heaviest path ending at v.
+    let mut best = vec![W::zero(); n];
+    let mut pred: Vec<Option<usize>> = vec![None; n];
+    for u in order {
+        let u = to_usize(u)?;
+        for (v, &w) in g.row_with_values(u, weights) {
+            let v = to_usize(v)?;
+            let cand = best[u] + w;
+            if cand > best[v] {
+                best[v] = cand;
+                pred[v] = Some(u);
+            }
+        }
+    }
+    let Some(end) = (0..n).reduce(|a, b| if best[b] > best[a] { b } else { a }) else {
+        return Ok((W::zero(), Vec::new()));
+    };
+    let mut path = vec![from_usize(end)?];
+    let mut x = end;
+    while let Some(p) = pred[x] {
+        path.push(from_usize(p)?);
+        x = p;
+    }
+    path.reverse();
+    Ok((best[end], path))
+}
+
+#[cfg(test)]
+mod tests {
+    use rand::rngs::StdRng;
+    use rand::seq::SliceRandom;
+    use rand::{Rng, SeedableRng};
+
+    use super::*;
+
+    fn sample() -> GraphMatrix<u32> {
+        GraphMatrix::from_edgelist(vec![(0, 1), (0, 2), (1, 3), (2, 3), (3, 4), (5, 4)]).unwrap()
+    }
+
+    /// A random DAG: edges go from lower to higher ranks, and ranks are shuffled ids.
+    fn random_dag(n: u32, m: usize, seed: u64) -> GraphMatrix<u32> {
+        let mut rng = StdRng::seed_from_u64(seed);
+        let mut id: Vec<u32> = (0..n).collect();
+        id.shuffle(&mut rng);
+        let edges = (0..m)
+            .filter_map(|_| {
+                let (a, b) = (rng.gen_range(0..n), rng.gen_range(0..n));
+                (a < b).then(|| (id[a as usize], id[b as usize]))
+            })
+            .collect();
+        GraphMatrix::from_edgelist_with_dims(edges, n as usize, n as usize).unwrap()
+    }
+
+    fn check_cycle(g: &GraphMatrix<u32>, err: GraphMatrixError) {
+        let GraphMatrixError::Cycle {cycle} = err else { panic!("expected a cycle: {:?}", err) };
+        assert!(!cycle.is_empty());
+        for i in 0..cycle.len() {
+            let (u, v) = (cycle[i], cycle[(i + 1) % cycle.len()]);
+            assert!(g.has_index(u as u32, v as u32).unwrap(), "no edge {} -> {}", u, v);
+        }
+    }
+
+    #[test]
+    fn known_dag() {
+        let g = sample();
+        assert!(is_dag(&g).unwrap());
+        assert_eq!(topological_sort(&g).unwrap(), vec![0, 5, 1, 2, 3, 4]);
+        assert_eq!(levels(&g).unwrap(), vec![0, 1, 1, 2, 3, 0]);
+        assert_eq!(longest_path(&g).unwrap(), vec![0, 1, 3, 4]);
+        let weights = [1, 5, 1, 1, 1, 9];
+        assert_eq!(longest_weighted_path(&g, &weights).unwrap(), (9, vec![5, 4]));
+        let weights = [1, 5, 1, 1, 1, 2];
+        assert_eq!(longest_weighted_path(&g, &weights).unwrap(), (7, vec![0, 2, 3, 4]));
+    }
+
+    #[test]
+    fn cyclic_graphs_report_a_closed_cycle() {
+        let edges = vec![(0, 1), (0, 2), (1, 3), (2, 3), (3, 4), (5, 4), (4, 1)];
+        let g = GraphMatrix::<u32>::from_edgelist(edges).unwrap();
+        assert!(!is_dag(&g).unwrap());
+        check_cycle(&g, topological_sort(&g).unwrap_err());
+        check_cycle(&g, levels(&g).unwrap_err());
+        check_cycle(&g, longest_path(&g).unwrap_err());
+        let g = GraphMatrix::<u32>::from_edgelist(vec![(0, 1), (2, 2)]).unwrap();
+        assert_eq!(topological_sort(&g).unwrap_err(), GraphMatrixError::Cycle {cycle: vec![2]});
+    }
+
+    #[test]
+    fn random_dags_sort_and_level() {
+        for seed in 0..5 {
+            let g = random_dag(200, 800, seed);
+            let order = topological_sort(&g).unwrap();
+            let mut position = vec![0; 200];
+            for (i, &v) in order.iter().enumerate() {
+                position[v as usize] = i;
+            }
+            let level = levels(&g).unwrap();
+            for (u, v) in g.iter() {
+                assert!(position[u as usize] < position[v as usize]);
+                assert!(level[u as usize] < level[v as usize]);
+            }
+            let path = longest_path(&g).unwrap();
+            assert_eq!(path.len(), level.iter().max().unwrap() + 1);
+            assert!(path.windows(2).all(|w| g.has_index(w[0], w[1]).unwrap()));
+            let mut cyclic: Vec<(u32, u32)> = g.iter().collect();
+            cyclic.push((path[path.len() - 1], path[0]));
+            let g = GraphMatrix::from_edgelist_with_dims(cyclic, 200, 200).unwrap();
+            assert!(!is_dag(&g).unwrap());
+            check_cycle(&g, topological_sort(&g).unwrap_err());
+        }
+    }
+}
@@ -0,0 +1,195 @@
+use std::borrow::Cow;
+use std::fmt;
+
+use num::PrimInt;
+
+/// Errors returned when building or querying a GraphMatrix. With the `serde` feature they can
+/// be serialized and deserialized, for example to report them across a process boundary.
+#[derive(Debug, Clone, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub enum GraphMatrixError {
+    /// An index could not be represented as a `usize` (for example, a negative value of a
+    /// signed index type), or an id read from a file does not fit in the index type. `edge`
+    /// is the position in the input edge list, and `line` the (1-based) line of the input
+    /// file, if any.
+    InvalidIndex { index: i128, edge: Option<usize>, line: Option<usize> },
+    /// An index was not less than the dimension it was checked against. `edge` is the
+    /// position in the input edge list, if any.
+    BoundsError { index: usize, bound: usize, edge: Option<usize> },
+    /// An operand's length did not match the dimension it is combined with.
+    DimensionMismatch { expected: usize, found: usize },
+    /// An edge weight was negative (or not comparable) where only non-negative weights are
+    /// allowed. `edge` is the position of the weight.
+    NegativeWeight { edge: usize },
+    /// A cycle of negative total weight is reachable, so shortest paths are undefined. `cycle`
+    /// lists its vertices in order.
+    NegativeCycle { cycle: Vec<usize> },
+    /// The graph has a cycle where a DAG is required. `cycle` lists its vertices in order.
+    Cycle { cycle: Vec<usize> },
+    /// An algorithm parameter was out of its valid range.
+    InvalidParameter { reason: Cow<'static, str> },
+    /// The input held no elements, so no dimensions could be inferred.
+    EmptyInput,
+    /// A value does not fit in the index type `T`.
+    Overflow { value: usize },
+    /// The compressed row structure violates an invariant, optionally at a given row.
+    CorruptStructure { row: Option<usize>, reason: Cow<'static, str> },
+    /// Reading or writing failed. `kind` and `message` are taken from the underlying
+    /// `std::io::Error`, which can be neither cloned nor compared.
+    Io {
+        #[cfg_attr(feature = "serde", serde(with = "crate::serde_impls::error_kind"))]
+        kind: std::io::ErrorKind,
+        message: String,
+    },
+    /// Input text was malformed at the given (1-based) line.
+    Parse { line: usize, reason: Cow<'static, str> },
+    /// Binary input was not in the expected format, or failed its checksum.
+    InvalidFormat { reason: Cow<'static, str> },
+}
+
+impl GraphMatrixError {
+    pub(crate) fn invalid_index<T: PrimInt>(index: T, edge: Option<usize>) -> Self {
+        // only u128 values above i128::MAX can miss here, and those are clamped.
+        let index = index.to_i128().unwrap_or(i128::MAX);
+        GraphMatrixError::InvalidIndex { index, edge, line: None }
+    }
+
+    pub(crate) fn invalid_parameter(reason: &'static str) -> Self {
+        GraphMatrixError::InvalidParameter { reason: Cow::Borrowed(reason) }
+    }
+}
+
+impl fmt::Display for GraphMatrixError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            GraphMatrixError::InvalidIndex { index, edge, line } => {
+                write!(f, "invalid index {}", index)?;
+                if let Some(e) = edge {
+                    write!(f, " in edge {}", e)?;
+                }
+                if let Some(l) = line {
+                    write!(f, " on line {}", l)?;
+                }
+                Ok(())
+            }
+            GraphMatrixError::BoundsError { index, bound, edge } => {
+                write!(f, "index {} out of bounds for dimension {}", index, bound)?;
+                if let Some(e) = edge {
+                    write!(f, " in edge {}", e)?;
+                }
+                Ok(())
+            }
+            GraphMatrixError::DimensionMismatch { expected, found } => {
+                write!(f, "dimension mismatch: expected {}, found {}", expected, found)
+            }
+            GraphMatrixError::NegativeWeight { edge } => {
+                write!(f, "negative weight on edge {}", edge)
+            }
+            GraphMatrixError::NegativeCycle { cycle } => {
+                write!(f, "negative cycle through {} vertices", cycle.len())
+            }
+            GraphMatrixError::Cycle { cycle } => {
+                write!(f, "graph is not acyclic: cycle of length {}", cycle.len())
+            }
+            GraphMatrixError::InvalidParameter { reason } => {
+                write!(f, "invalid parameter: {}", reason)
+            }
+            GraphMatrixError::EmptyInput => write!(f, "empty input"),
+            GraphMatrixError::Overflow { value } => {
+                write!(f, "value {} does not fit in the index type", value)
+            }
+            GraphMatrixError::CorruptStructure { row, reason } => {
+                write!(f, "corrupt CSR structure")?;
+                if let Some(r) = row {
+                    write!(f, " at row {}", r)?;
+                }
+                write!(f, ": {}", reason)
+            }
+            GraphMatrixError::Io { message, .. } => write!(f, "I/O error: {}", message),
+            GraphMatrixError::Parse { line, reason } => {
+                write!(f, "parse error on line {}: {}", line, reason)
+            }
+            GraphMatrixError::InvalidFormat { reason } => {
+                write!(f, "invalid binary format: {}", reason)
+            }
+        }
+    }
+}
+
+impl std::error::Error for GraphMatrixError {}
+
+impl From<std::io::Error> for GraphMatrixError {
+    fn from(e: std::io::Error) -> Self {
+        GraphMatrixError::Io { kind: e.kind(), message: e.to_string() }
+    }
+}
+
+/// Converts an index to `usize`.
+pub(crate) fn to_usize<T: PrimInt>(v: T) -> Result<usize, GraphMatrixError> {
+    v.to_usize().ok_or_else(|| GraphMatrixError::invalid_index(v, None))
+}
+
+/// Converts a stored index to `usize`. Indices were validated on construction, so the
+/// conversion can't fail.
+pub(crate) fn index<T: PrimInt>(v: T) -> usize {
+    v.to_usize().expect("index fits in usize")
+}
+
+/// Converts a `usize` to the index type `T`.
+pub(crate) fn from_usize<T: PrimInt>(v: usize) -> Result<T, GraphMatrixError> {
+    T::from(v).ok_or(GraphMatrixError::Overflow { value: v })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn display_names_the_offending_values() {
+        let cases = [
+            (GraphMatrixError::invalid_index(-3, Some(2)), "invalid index -3 in edge 2"),
+            (
+                GraphMatrixError::InvalidIndex {index: 7, edge: None, line: Some(4)},
+                "invalid index 7 on line 4",
+            ),
+            (
+                GraphMatrixError::BoundsError {index: 5, bound: 5, edge: Some(1)},
+                "index 5 out of bounds for dimension 5 in edge 1",
+            ),
+            (
+                GraphMatrixError::DimensionMismatch {expected: 4, found: 3},
+                "dimension mismatch: expected 4, found 3",
+            ),
+            (GraphMatrixError::NegativeWeight {edge: 6}, "negative weight on edge 6"),
+            (
+                GraphMatrixError::NegativeCycle {cycle: vec![0, 1, 2]},
+                "negative cycle through 3 vertices",
+            ),
+            (
+                GraphMatrixError::Cycle {cycle: vec![0, 1, 2]},
+                "graph is not acyclic: cycle of length 3",
+            ),
+            (
+                GraphMatrixError::invalid_parameter("damping must be in [0, 1]"),
+                "invalid parameter: damping must be in [0, 1]",
+            ),
+            (GraphMatrixError::Overflow {value: 300}, "value 300 does not fit in the index type"),
+            (
+                GraphMatrixError::CorruptStructure {row: Some(3), reason: "bad indptr".into()},
+                "corrupt CSR structure at row 3: bad indptr",
+            ),
+            (std::io::Error::other("disk full").into(), "I/O error: disk full"),
+            (
+                GraphMatrixError::Parse {line: 3, reason: "expected two ids".into()},
+                "parse error on line 3: expected two ids",
+            ),
+            (
+                GraphMatrixError::InvalidFormat {reason: "bad magic number".into()},
+                "invalid binary format: bad magic number",
+            ),
+        ];
+        for (err, message) in cases {
+            assert_eq!(err.to_string(), message);
+        }
+    }
+}
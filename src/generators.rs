@@ -0,0 +1,514 @@
+//! Generators for synthetic undirected graphs, returned as symmetric GraphMatrices (every
+//! edge is stored in both directions) without self-loops. The random generators take any
+//! `rand::Rng`, so seeding it, for example with `StdRng::seed_from_u64`, makes the output
+//! reproducible.
+
+use std::collections::{BTreeMap, HashSet};
+
+use num::PrimInt;
+use rand::seq::SliceRandom;
+use rand::Rng;
+
+use crate::{check_dim_fits, from_usize, GraphMatrix, GraphMatrixError};
+
+/// Builds a symmetric GraphMatrix on `n` vertices holding both directions of every edge.
+fn undirected<T: PrimInt>(
+    n: usize,
+    edges: impl IntoIterator<Item = (usize, usize)>,
+) -> Result<GraphMatrix<T>, GraphMatrixError> {
+    check_dim_fits::<T>(n)?;
+    let mut edgelist = Vec::new();
+    for (u, v) in edges {
+        let (tu, tv) = (from_usize(u)?, from_usize(v)?);
+        edgelist.push((tu, tv));
+        edgelist.push((tv, tu));
+    }
+    GraphMatrix::from_edgelist_with_dims(edgelist, n, n)
+}
+
+fn check_probability(p: f64, reason: &'static str) -> Result<(), GraphMatrixError> {
+    if !(0.0..=1.0).contains(&p) {
+        return Err(GraphMatrixError::invalid_parameter(reason))
+    }
+    Ok(())
+}
+
+/// Returns `n * (n - 1) / 2`, the number of edges in a complete graph on `n` vertices.
+fn max_edges(n: usize) -> Result<usize, GraphMatrixError> {
+    (n / 2)
+        .checked_mul(n.saturating_sub(1))
+        .and_then(|x| if n % 2 == 1 { x.checked_add(n.saturating_sub(1) / 2) } else { Some(x) })
+        .ok_or(GraphMatrixError::invalid_parameter("too many vertices"))
+}
+
+/// An Erdős–Rényi G(n, p) graph, in which each of the possible edges is present
+/// independently with probability `p`. Runs in O(V + E) by skipping geometrically
+/// distributed runs of absent edges (Batagelj and Brandes).
+pub fn erdos_renyi_gnp<T, R>(
+    n: usize,
+    p: f64,
+    rng: &mut R,
+) -> Result<GraphMatrix<T>, GraphMatrixError>
+where
+    T: PrimInt,
+    R: Rng + ?Sized,
+{
+    check_probability(p, "p must be in [0, 1]")?;
+    if p == 1.0 {
+        return complete(n);
+    }
+    let mut edges = Vec::new();
+    if p > 0.0 {
+        // ln(1 - p) directly would round to zero for tiny p, and the skips would never move.
+        let lp = (-p).ln_1p();
+        // the next candidate edge is (v, w) with w < v, in row-major order.
+        let (mut v, mut w) = (1usize, 0usize);
+        loop {
+            let r: f64 = rng.gen();
+            // float-to-int casts saturate, so an enormous skip just runs off the end.
+            w = w.saturating_add(((1.0 - r).ln() / lp).floor() as usize);
+            while v < n && w >= v {
+                w -= v;
+                v += 1;
+            }
+            if v >= n {
+                break;
+            }
+            edges.push((v, w));
+            w += 1;
+        }
+    }
+    undirected(n, edges)
+}
+
+/// An Erdős–Rényi G(n, m) graph, chosen uniformly among graphs with `n` vertices and `m`
+/// edges. When `m` is more than half of the possible edges, the edges to leave out are
+/// sampled instead.
+pub fn erdos_renyi_gnm<T, R>(
+    n: usize,
+    m: usize,
+    rng: &mut R,
+) -> Result<GraphMatrix<T>, GraphMatrixError>
+where
+    T: PrimInt,
+    R: Rng + ?Sized,
+{
+    let max = max_edges(n)?;
+    if m > max {
+        return Err(GraphMatrixError::invalid_parameter("m exceeds n(n - 1)/2"))
+    }
+    let dense = m > max / 2;
+    let target = if dense { max - m } else { m };
+    let mut chosen = HashSet::with_capacity(target);
+    while chosen.len() < target {
+        let u = rng.gen_range(0..n);
+        let v = rng.gen_range(0..n);
+        if u != v {
+            chosen.insert((u.min(v), u.max(v)));
+        }
+    }
+    if dense {
+        let all = (0..n).flat_map(|v| (0..v).map(move |u| (u, v)));
+        undirected(n, all.filter(|e| !chosen.contains(e)))
+    } else {
+        undirected(n, chosen)
+    }
+}
+
+/// A Barabási–Albert preferential attachment graph. It starts from a complete graph on
+/// `m + 1` vertices. Each later vertex joins with edges to `m` distinct earlier vertices,
+/// each picked with probability proportional to its degree. Requires `1 <= m < n`.
+pub fn barabasi_albert<T, R>(
+    n: usize,
+    m: usize,
+    rng: &mut R,
+) -> Result<GraphMatrix<T>, GraphMatrixError>
+where
+    T: PrimInt,
+    R: Rng + ?Sized,
+{
+    if m == 0 || m >= n {
+        return Err(GraphMatrixError::invalid_parameter("m must be in [1, n)"))
+    }
+    let mut edges: Vec<(usize, usize)> =
+        (0..=m).flat_map(|v| (0..v).map(move |u| (u, v))).collect();
+    // every vertex appears here once per incident edge, so a uniform pick is degree-biased.
+    let mut ends: Vec<usize> = edges.iter().flat_map(|&(u, v)| [u, v]).collect();
+    let mut targets = Vec::with_capacity(m);
+    for v in m + 1..n {
+        targets.clear();
+        while targets.len() < m {
+            let u = ends[rng.gen_range(0..ends.len())];
+            if !targets.contains(&u) {
+                targets.push(u);
+            }
+        }
+        for &u in &targets {
+            edges.push((u, v));
+            ends.push(u);
+            ends.push(v);
+        }
+    }
+    undirected(n, edges)
+}
+
+/// A Watts–Strogatz small-world graph. It starts from a ring in which each vertex is joined
+/// to its `k` nearest neighbors, `k / 2` on each side. Each ring edge `(u, u + j)` then moves
+/// with probability `beta` to `(u, w)`, where `w` is uniform among vertices not yet adjacent
+/// to `u`. Requires an even `k < n`.
+pub fn watts_strogatz<T, R>(
+    n: usize,
+    k: usize,
+    beta: f64,
+    rng: &mut R,
+) -> Result<GraphMatrix<T>, GraphMatrixError>
+where
+    T: PrimInt,
+    R: Rng + ?Sized,
+{
+    if k % 2 == 1 || k >= n.max(1) {
+        return Err(GraphMatrixError::invalid_parameter("k must be even and less than n"))
+    }
+    check_probability(beta, "beta must be in [0, 1]")?;
+    let mut adj: Vec<HashSet<usize>> = vec![HashSet::new(); n];
+    for u in 0..n {
+        for j in 1..=k / 2 {
+            let v = (u + j) % n;
+            adj[u].insert(v);
+            adj[v].insert(u);
+        }
+    }
+    for j in 1..=k / 2 {
+        for u in 0..n {
+            let v = (u + j) % n;
+            if !rng.gen_bool(beta) || adj[u].len() == n - 1 {
+                continue;
+            }
+            let w = loop {
+                let w = rng.gen_range(0..n);
+                if w != u && !adj[u].contains(&w) {
+                    break w;
+                }
+            };
+            adj[u].remove(&v);
+            adj[v].remove(&u);
+            adj[u].insert(w);
+            adj[w].insert(u);
+        }
+    }
+    let edges = adj.iter().enumerate().flat_map(|(u, ns)| ns.iter().map(move |&v| (u, v)));
+    undirected(n, edges.filter(|&(u, v)| u < v))
+}
+
+/// Quadrant probabilities for the R-MAT generator. The fourth quadrant gets the remaining
+/// `1 - a - b - c`.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct RmatConfig {
+    pub a: f64,
+    pub b: f64,
+    pub c: f64,
+}
+
+impl Default for RmatConfig {
+    /// The Graph500 parameters.
+    fn default() -> Self {
+        RmatConfig {a: 0.57, b: 0.19, c: 0.19}
+    }
+}
+
+/// An R-MAT (recursive matrix) graph on `2^scale` vertices, as used by the Graph500
+/// Kronecker generator. Each of `edge_factor * 2^scale` edges picks its adjacency-matrix
+/// quadrant one bit at a time, following `config`. Vertex ids are then randomly permuted.
+/// Self-loops and repeated edges are dropped, so the result may have fewer edges.
+pub fn rmat<T, R>(
+    scale: u32,
+    edge_factor: usize,
+    config: &RmatConfig,
+    rng: &mut R,
+) -> Result<GraphMatrix<T>, GraphMatrixError>
+where
+    T: PrimInt,
+    R: Rng + ?Sized,
+{
+    let RmatConfig {a, b, c} = *config;
+    if [a, b, c].iter().any(|p| !(0.0..=1.0).contains(p)) || a + b + c > 1.0 {
+        let reason = "quadrant probabilities must be non-negative and sum to at most 1";
+        return Err(GraphMatrixError::invalid_parameter(reason))
+    }
+    let n = 1usize
+        .checked_shl(scale)
+        .filter(|&n| n.checked_mul(edge_factor).is_some())
+        .ok_or(GraphMatrixError::invalid_parameter("scale is too large"))?;
+    check_dim_fits::<T>(n)?;
+    let mut perm: Vec<usize> = (0..n).collect();
+    perm.shuffle(rng);
+    let mut edges = Vec::with_capacity(n * edge_factor);
+    for _ in 0..n * edge_factor {
+        let (mut u, mut v) = (0, 0);
+        for bit in (0..scale).rev() {
+            let r: f64 = rng.gen();
+            let (down, right) = if r < a {
+                (0, 0)
+            } else if r < a + b {
+                (0, 1)
+            } else if r < a + b + c {
+                (1, 0)
+            } else {
+                (1, 1)
+            };
+            u |= down << bit;
+            v |= right << bit;
+        }
+        if u != v {
+            edges.push((perm[u], perm[v]));
+        }
+    }
+    undirected(n, edges)
+}
+
+/// Returns `dims.iter().product()`, or an error if it overflows.
+fn grid_size(dims: &[usize]) -> Result<usize, GraphMatrixError> {
+    dims.iter()
+        .try_fold(1usize, |acc, &d| acc.checked_mul(d))
+        .ok_or(GraphMatrixError::invalid_parameter("grid is too large"))
+}
+
+/// A `rows` x `cols` grid, with vertex `r * cols + c` joined to its horizontal and vertical
+/// neighbors.
+pub fn grid_2d<T: PrimInt>(rows: usize, cols: usize) -> Result<GraphMatrix<T>, GraphMatrixError> {
+    let n = grid_size(&[rows, cols])?;
+    let mut edges = Vec::new();
+    for r in 0..rows {
+        for c in 0..cols {
+            let v = r * cols + c;
+            if c + 1 < cols {
+                edges.push((v, v + 1));
+            }
+            if r + 1 < rows {
+                edges.push((v, v + cols));
+            }
+        }
+    }
+    undirected(n, edges)
+}
+
+/// An `x` x `y` x `z` grid, with vertex `(i * y + j) * z + k` joined to its neighbors along
+/// each axis.
+pub fn grid_3d<T: PrimInt>(
+    x: usize,
+    y: usize,
+    z: usize,
+) -> Result<GraphMatrix<T>, GraphMatrixError> {
+    let n = grid_size(&[x, y, z])?;
+    let mut edges = Vec::new();
+    for i in 0..x {
+        for j in 0..y {
+            for k in 0..z {
+                let v = (i * y + j) * z + k;
+                if k + 1 < z {
+                    edges.push((v, v + 1));
+                }
+                if j + 1 < y {
+                    edges.push((v, v + z));
+                }
+                if i + 1 < x {
+                    edges.push((v, v + y * z));
+                }
+            }
+        }
+    }
+    undirected(n, edges)
+}
+
+/// The complete graph on `n` vertices.
+pub fn complete<T: PrimInt>(n: usize) -> Result<GraphMatrix<T>, GraphMatrixError> {
+    max_edges(n)?;
+    undirected(n, (0..n).flat_map(|v| (0..v).map(move |u| (u, v))))
+}
+
+/// The path `0 - 1 - ... - (n - 1)`.
+pub fn path<T: PrimInt>(n: usize) -> Result<GraphMatrix<T>, GraphMatrixError> {
+    undirected(n, (1..n).map(|v| (v - 1, v)))
+}
+
+/// The cycle `0 - 1 - ... - (n - 1) - 0`. Requires `n >= 3`.
+pub fn cycle<T: PrimInt>(n: usize) -> Result<GraphMatrix<T>, GraphMatrixError> {
+    if n < 3 {
+        return Err(GraphMatrixError::invalid_parameter("a cycle needs at least 3 vertices"))
+    }
+    undirected(n, (1..n).map(|v| (v - 1, v)).chain([(n - 1, 0)]))
+}
+
+/// The star with center 0 and leaves `1..n`.
+pub fn star<T: PrimInt>(n: usize) -> Result<GraphMatrix<T>, GraphMatrixError> {
+    undirected(n, (1..n).map(|v| (0, v)))
+}
+
+/// A random `d`-regular graph on `n` vertices, sampled by the pairing method of Steger and
+/// Wormald. Each round shuffles the unmatched endpoints and pairs them up, keeping every
+/// pair that forms a new edge. Generation restarts if the leftovers can no longer be
+/// paired. Requires `d < n` and `n * d` even.
+pub fn random_regular<T, R>(
+    n: usize,
+    d: usize,
+    rng: &mut R,
+) -> Result<GraphMatrix<T>, GraphMatrixError>
+where
+    T: PrimInt,
+    R: Rng + ?Sized,
+{
+    if d >= n.max(1) || n.checked_mul(d).is_none_or(|s| s % 2 == 1) {
+        return Err(GraphMatrixError::invalid_parameter("need d < n and n * d even"))
+    }
+    loop {
+        if let Some(edges) = try_pairing(n, d, rng) {
+            return undirected(n, edges);
+        }
+    }
+}
+
+/// One attempt at pairing up `d` endpoints per vertex into a simple graph.
+fn try_pairing<R: Rng + ?Sized>(n: usize, d: usize, rng: &mut R) -> Option<Vec<(usize, usize)>> {
+    let mut edges = HashSet::with_capacity(n * d / 2);
+    let mut stubs: Vec<usize> = (0..n).flat_map(|v| std::iter::repeat_n(v, d)).collect();
+    while !stubs.is_empty() {
+        stubs.shuffle(rng);
+        // a BTreeMap keeps the leftover order, and so the output, reproducible.
+        let mut leftover: BTreeMap<usize, usize> = BTreeMap::new();
+        for pair in stubs.chunks_exact(2) {
+            let (u, v) = (pair[0].min(pair[1]), pair[0].max(pair[1]));
+            if u != v && edges.insert((u, v)) {
+                continue;
+            }
+            *leftover.entry(u).or_default() += 1;
+            *leftover.entry(v).or_default() += 1;
+        }
+        // give up unless some leftover pair could still become a new edge.
+        let vs: Vec<usize> = leftover.keys().copied().collect();
+        let pairable = vs.is_empty()
+            || vs
+                .iter()
+                .enumerate()
+                .any(|(i, &u)| vs[..i].iter().any(|&w| !edges.contains(&(w, u))));
+        if !pairable {
+            return None;
+        }
+        stubs = leftover.into_iter().flat_map(|(v, c)| std::iter::repeat_n(v, c)).collect();
+    }
+    Some(edges.into_iter().collect())
+}
+
+#[cfg(test)]
+mod tests {
+    use rand::rngs::StdRng;
+    use rand::SeedableRng;
+
+    use super::*;
+
+    /// Checks that `g` is square, symmetric and loop-free, and returns its vertex degrees.
+    fn degrees(g: &GraphMatrix<u32>) -> Vec<usize> {
+        let (n, ncols) = g.dims();
+        assert_eq!(n, ncols);
+        for (u, v) in g.iter() {
+            assert_ne!(u, v);
+            assert!(g.has_index(v, u).unwrap());
+        }
+        (0..n).map(|v| g.row_slice(v).len()).collect()
+    }
+
+    fn edges(g: &GraphMatrix<u32>) -> Vec<(u32, u32)> {
+        g.iter().collect()
+    }
+
+    #[test]
+    fn seeded_generators_are_reproducible() {
+        type Generator = fn(&mut StdRng) -> GraphMatrix<u32>;
+        let generators: [Generator; 6] = [
+            |rng| erdos_renyi_gnp(200, 0.05, rng).unwrap(),
+            |rng| erdos_renyi_gnm(200, 500, rng).unwrap(),
+            |rng| barabasi_albert(200, 3, rng).unwrap(),
+            |rng| watts_strogatz(200, 4, 0.3, rng).unwrap(),
+            |rng| rmat(8, 4, &RmatConfig::default(), rng).unwrap(),
+            |rng| random_regular(200, 3, rng).unwrap(),
+        ];
+        for generate in generators {
+            let g = generate(&mut StdRng::seed_from_u64(19));
+            degrees(&g);
+            assert_eq!(edges(&g), edges(&generate(&mut StdRng::seed_from_u64(19))));
+            assert_ne!(edges(&g), edges(&generate(&mut StdRng::seed_from_u64(20))));
+        }
+    }
+
+    #[test]
+    fn random_generators_hit_their_edge_counts() {
+        let mut rng = StdRng::seed_from_u64(19);
+        // 4950 possible edges, so 4000 takes the dense path.
+        for m in [0, 100, 4000, 4950] {
+            let g: GraphMatrix<u32> = erdos_renyi_gnm(100, m, &mut rng).unwrap();
+            assert_eq!(degrees(&g).len(), 100);
+            assert_eq!(g.ne(), 2 * m);
+        }
+        let g: GraphMatrix<u32> = barabasi_albert(100, 3, &mut rng).unwrap();
+        assert_eq!(g.ne(), 2 * (6 + 96 * 3));
+        assert!(degrees(&g).iter().all(|&d| d >= 3));
+        for beta in [0.0, 0.5, 1.0] {
+            let g: GraphMatrix<u32> = watts_strogatz(100, 6, beta, &mut rng).unwrap();
+            assert_eq!(g.ne(), 2 * 300);
+            if beta == 0.0 {
+                assert_eq!(degrees(&g), vec![6; 100]);
+            }
+        }
+        let g: GraphMatrix<u32> = random_regular(101, 4, &mut rng).unwrap();
+        assert_eq!(degrees(&g), vec![4; 101]);
+        let g: GraphMatrix<u32> = erdos_renyi_gnp(30, 1.0, &mut rng).unwrap();
+        assert_eq!(g.ne(), 30 * 29);
+        let g: GraphMatrix<u32> = erdos_renyi_gnp(30, 0.0, &mut rng).unwrap();
+        assert_eq!(g.ne(), 0);
+    }
+
+    #[test]
+    fn deterministic_generators() {
+        let g: GraphMatrix<u32> = grid_2d(3, 4).unwrap();
+        let d = degrees(&g);
+        assert_eq!(g.ne(), 2 * (3 * 3 + 2 * 4));
+        assert_eq!((d[0], d[1], d[5], d[11]), (2, 3, 4, 2));
+        let g: GraphMatrix<u32> = grid_3d(2, 3, 4).unwrap();
+        let d = degrees(&g);
+        assert_eq!(g.ne(), 2 * (12 + 16 + 18));
+        assert_eq!((d[0], d[5], d[23]), (3, 5, 3));
+        let g: GraphMatrix<u32> = complete(6).unwrap();
+        assert_eq!(degrees(&g), vec![5; 6]);
+        let g: GraphMatrix<u32> = path(5).unwrap();
+        assert_eq!(degrees(&g), vec![1, 2, 2, 2, 1]);
+        let g: GraphMatrix<u32> = cycle(5).unwrap();
+        assert_eq!(degrees(&g), vec![2; 5]);
+        let g: GraphMatrix<u32> = star(6).unwrap();
+        assert_eq!(degrees(&g), vec![5, 1, 1, 1, 1, 1]);
+        assert_eq!(edges(&g)[..5], [(0, 1), (0, 2), (0, 3), (0, 4), (0, 5)]);
+    }
+
+    #[test]
+    fn rejects_invalid_parameters() {
+        let mut rng = StdRng::seed_from_u64(19);
+        let invalid = |r: Result<GraphMatrix<u32>, GraphMatrixError>| {
+            matches!(r, Err(GraphMatrixError::InvalidParameter { .. }))
+        };
+        assert!(invalid(erdos_renyi_gnp(10, 1.5, &mut rng)));
+        assert!(invalid(erdos_renyi_gnm(10, 46, &mut rng)));
+        assert!(invalid(barabasi_albert(10, 10, &mut rng)));
+        assert!(invalid(watts_strogatz(10, 3, 0.5, &mut rng)));
+        let config = RmatConfig {a: 0.5, b: 0.5, c: 0.5};
+        assert!(invalid(rmat(4, 2, &config, &mut rng)));
+        assert!(invalid(random_regular(5, 3, &mut rng)));
+        assert!(invalid(cycle(2)));
+    }
+
+    #[test]
+    fn gnp_with_tiny_p_is_sparse() {
+        for p in [1e-17, 1e-20] {
+            let mut rng = StdRng::seed_from_u64(1);
+            let g: GraphMatrix<u32> = erdos_renyi_gnp(100, p, &mut rng).unwrap();
+            assert_eq!(g.ne(), 0);
+        }
+    }
+}
@@ -0,0 +1,75 @@
+//! Reading and writing graphs in external file formats. Text readers take any `BufRead`,
+//! and writers any `Write`. Wrap files in `BufReader` / `BufWriter` for reasonable speed.
+//!
+//! # Binary format
+//!
+//! `GraphMatrix::write_to` and `GraphMatrix::read_from` use a compact binary format, which
+//! loads without any parsing. All integers are little-endian:
+//!
+//! | bytes | contents                                        |
+//! |-------|-------------------------------------------------|
+//! | 8     | magic `b"GRMATRIX"`                             |
+//! | 4     | format version (`u32`, currently 1)             |
+//! | 1     | index width in bytes (1, 2, 4, 8 or 16)         |
+//! | 3     | reserved, must be zero                          |
+//! | 8     | `nrows` (`u64`)                                 |
+//! | 8     | `ncols` (`u64`)                                 |
+//! | 8     | `nnz` (`u64`)                                   |
+//! | ...   | `nrows + 1` row pointers (`u64` each)           |
+//! | ...   | `nnz` column indices (index width each)         |
+//! | 4     | CRC-32 of everything before it (`u32`)          |
+//!
+//! Row pointers start 8-byte aligned, so `GraphMatrixView::from_bytes` can use a file in
+//! place when its indices are no wider than 8 bytes. With the `mmap` feature,
+//! `MappedGraphMatrix` maps such a file and hands out views of it.
+
+use std::io::BufRead;
+
+use crate::GraphMatrixError;
+
+mod binary;
+mod edge_list;
+mod matrix_market;
+
+#[cfg(feature = "mmap")]
+pub use binary::MappedGraphMatrix;
+pub use edge_list::{read_edge_list, read_edge_list_valued, EdgeListConfig};
+pub use matrix_market::{
+    read_matrix_market, read_matrix_market_valued, write_matrix_market,
+    write_matrix_market_valued, Field, Symmetry,
+};
+
+fn parse_error(line: usize, reason: &'static str) -> GraphMatrixError {
+    GraphMatrixError::Parse {line, reason: reason.into()}
+}
+
+/// Reads lines into a reused buffer, skipping blank lines and comments, and tracks the line
+/// number.
+struct Lines<R> {
+    reader: R,
+    comment: char,
+    buf: String,
+    line: usize,
+}
+
+impl<R: BufRead> Lines<R> {
+
+    fn new(reader: R, comment: char) -> Self {
+        Lines {reader, comment, buf: String::new(), line: 0}
+    }
+
+    /// Returns the next line and its number, including comments when `comments` is set.
+    fn next_line(&mut self, comments: bool) -> Result<Option<(usize, &str)>, GraphMatrixError> {
+        loop {
+            self.buf.clear();
+            if self.reader.read_line(&mut self.buf)? == 0 {
+                return Ok(None);
+            }
+            self.line += 1;
+            let trimmed = self.buf.trim();
+            if !trimmed.is_empty() && (comments || !trimmed.starts_with(self.comment)) {
+                return Ok(Some((self.line, self.buf.trim())));
+            }
+        }
+    }
+}
@@ -0,0 +1,470 @@
+use std::io::{Read, Write};
+
+use crc32fast::Hasher;
+use num::PrimInt;
+
+use crate::{validate_csr, GraphMatrix, GraphMatrixError, GraphMatrixView, PlainIndex};
+
+const MAGIC: [u8; 8] = *b"GRMATRIX";
+const VERSION: u32 = 1;
+const HEADER_LEN: usize = 40;
+/// The number of elements encoded or decoded at a time.
+const CHUNK: usize = 1 << 16;
+
+fn invalid(reason: &'static str) -> GraphMatrixError {
+    GraphMatrixError::InvalidFormat {reason: reason.into()}
+}
+
+/// The dimensions recorded in a file header.
+struct Header {
+    width: usize,
+    nrows: usize,
+    ncols: usize,
+    nnz: usize,
+}
+
+fn parse_header(header: &[u8; HEADER_LEN]) -> Result<Header, GraphMatrixError> {
+    if header[..8] != MAGIC {
+        return Err(invalid("not a GraphMatrix file"))
+    }
+    let word = |at: usize| u64::from_le_bytes(header[at..at + 8].try_into().unwrap());
+    if u32::from_le_bytes(header[8..12].try_into().unwrap()) != VERSION {
+        return Err(invalid("unsupported version"))
+    }
+    let width = header[12] as usize;
+    if ![1, 2, 4, 8, 16].contains(&width) {
+        return Err(invalid("index width must be 1, 2, 4, 8 or 16 bytes"))
+    }
+    if header[13..16] != [0, 0, 0] {
+        return Err(invalid("reserved header bytes are not zero"))
+    }
+    let size = |x: u64| usize::try_from(x).map_err(|_| invalid("dimensions do not fit in usize"));
+    Ok(Header {width, nrows: size(word(16))?, ncols: size(word(24))?, nnz: size(word(32))?})
+}
+
+/// Writes bytes while feeding them to the checksum.
+struct Checked<W> {
+    inner: W,
+    hasher: Hasher,
+}
+
+impl<W: Write> Checked<W> {
+
+    fn write(&mut self, bytes: &[u8]) -> Result<(), GraphMatrixError> {
+        self.hasher.update(bytes);
+        Ok(self.inner.write_all(bytes)?)
+    }
+
+    /// Encodes `items` `width` bytes apiece, a chunk at a time.
+    fn write_all<I: Copy>(
+        &mut self,
+        items: &[I],
+        width: usize,
+        encode: impl Fn(I) -> u128,
+    ) -> Result<(), GraphMatrixError> {
+        let mut buf = Vec::with_capacity(CHUNK.min(items.len()) * width);
+        for chunk in items.chunks(CHUNK) {
+            buf.clear();
+            match width {
+                1 => encode_chunk::<_, 1>(chunk, &mut buf, &encode),
+                2 => encode_chunk::<_, 2>(chunk, &mut buf, &encode),
+                4 => encode_chunk::<_, 4>(chunk, &mut buf, &encode),
+                8 => encode_chunk::<_, 8>(chunk, &mut buf, &encode),
+                _ => encode_chunk::<_, 16>(chunk, &mut buf, &encode),
+            }
+            self.write(&buf)?;
+        }
+        Ok(())
+    }
+}
+
+/// Appends the low `W` bytes of each encoded item. Taking the width as a constant lets the
+/// loop compile down to plain stores.
+fn encode_chunk<I, const W: usize>(items: &[I], buf: &mut Vec<u8>, encode: impl Fn(I) -> u128)
+where
+    I: Copy,
+{
+    for &x in items {
+        buf.extend_from_slice(&encode(x).to_le_bytes()[..W]);
+    }
+}
+
+/// Reads bytes while feeding them to the checksum.
+struct Verified<R> {
+    inner: R,
+    hasher: Hasher,
+}
+
+impl<R: Read> Verified<R> {
+
+    fn read(&mut self, bytes: &mut [u8]) -> Result<(), GraphMatrixError> {
+        self.inner.read_exact(bytes)?;
+        self.hasher.update(bytes);
+        Ok(())
+    }
+
+    /// Decodes `len` items of `width` bytes apiece. The output grows only as data arrives, so
+    /// a corrupt length fails on a short read rather than on a huge allocation.
+    fn read_all<I>(
+        &mut self,
+        len: usize,
+        width: usize,
+        decode: impl Fn(u128) -> Result<I, GraphMatrixError>,
+    ) -> Result<Vec<I>, GraphMatrixError> {
+        let mut out = Vec::new();
+        let mut buf = vec![0u8; len.min(CHUNK) * width];
+        let mut left = len;
+        while left > 0 {
+            let n = left.min(CHUNK);
+            let bytes = &mut buf[..n * width];
+            self.read(bytes)?;
+            out.reserve(n);
+            match width {
+                1 => decode_chunk::<_, 1>(bytes, &mut out, &decode)?,
+                2 => decode_chunk::<_, 2>(bytes, &mut out, &decode)?,
+                4 => decode_chunk::<_, 4>(bytes, &mut out, &decode)?,
+                8 => decode_chunk::<_, 8>(bytes, &mut out, &decode)?,
+                _ => decode_chunk::<_, 16>(bytes, &mut out, &decode)?,
+            }
+            left -= n;
+        }
+        Ok(out)
+    }
+}
+
+/// Decodes `W`-byte items from `bytes` onto `out`, the counterpart of `encode_chunk`.
+fn decode_chunk<I, const W: usize>(
+    bytes: &[u8],
+    out: &mut Vec<I>,
+    decode: impl Fn(u128) -> Result<I, GraphMatrixError>,
+) -> Result<(), GraphMatrixError> {
+    for b in bytes.chunks_exact(W) {
+        let mut word = [0u8; 16];
+        word[..W].copy_from_slice(b);
+        out.push(decode(u128::from_le_bytes(word))?);
+    }
+    Ok(())
+}
+
+impl<T> GraphMatrix<T> where T: PrimInt {
+
+    /// Writes this matrix in the binary format described in the `io` module, with indices
+    /// stored at the width of `T`.
+    pub fn write_to<W: Write>(&self, writer: W) -> Result<(), GraphMatrixError> {
+        let width = std::mem::size_of::<T>();
+        let (nrows, ncols) = self.dims();
+        let mut header = Vec::with_capacity(HEADER_LEN);
+        header.extend_from_slice(&MAGIC);
+        header.extend_from_slice(&VERSION.to_le_bytes());
+        header.extend_from_slice(&[width as u8, 0, 0, 0]);
+        for x in [nrows, ncols, self.ne()] {
+            header.extend_from_slice(&(x as u64).to_le_bytes());
+        }
+        let mut w = Checked {inner: writer, hasher: Hasher::new()};
+        w.write(&header)?;
+        w.write_all(&self.indptr, 8, |p| p as u128)?;
+        // indices are non-negative, so their low bytes are the whole value.
+        w.write_all(&self.indices, width, |c| c.to_u128().expect("index is non-negative"))?;
+        let Checked {mut inner, hasher} = w;
+        Ok(inner.write_all(&hasher.finalize().to_le_bytes())?)
+    }
+
+    /// Reads a matrix written by `write_to`, checking the header and checksum and then
+    /// validating the CSR structure. Indices stored at a different width than `T` are
+    /// converted, returning `GraphMatrixError::Overflow` if one does not fit.
+    pub fn read_from<R: Read>(reader: R) -> Result<Self, GraphMatrixError> {
+        let mut r = Verified {inner: reader, hasher: Hasher::new()};
+        let mut header = [0u8; HEADER_LEN];
+        r.read(&mut header)?;
+        let Header {width, nrows, ncols, nnz} = parse_header(&header)?;
+        let too_large = || invalid("dimensions do not fit in usize");
+        let len = nrows.checked_add(1).ok_or_else(too_large)?;
+        let indptr = r.read_all(len, 8, |p| usize::try_from(p).map_err(|_| too_large()))?;
+        let indices = r.read_all(nnz, width, |c| {
+            T::from(c).ok_or_else(|| match usize::try_from(c) {
+                Ok(value) => GraphMatrixError::Overflow {value},
+                Err(_) => too_large(),
+            })
+        })?;
+        let mut crc = [0u8; 4];
+        r.inner.read_exact(&mut crc)?;
+        if u32::from_le_bytes(crc) != r.hasher.finalize() {
+            return Err(invalid("checksum mismatch"))
+        }
+        GraphMatrix::try_from_csr_with_ncols(indptr, indices, ncols)
+    }
+}
+
+/// Locates the row pointers and indices inside a whole file held in memory, checking the
+/// header, the length and the alignment of both arrays, but not the checksum or the CSR
+/// structure.
+fn split<T: PlainIndex>(bytes: &[u8]) -> Result<GraphMatrixView<'_, T>, GraphMatrixError> {
+    if cfg!(target_endian = "big") || std::mem::size_of::<usize>() != 8 {
+        return Err(invalid("zero-copy loading needs a 64-bit little-endian target"))
+    }
+    let header = bytes.get(..HEADER_LEN).ok_or(invalid("file is shorter than its header"))?;
+    let Header {width, nrows, ncols, nnz} = parse_header(header.try_into().unwrap())?;
+    if width != std::mem::size_of::<T>() {
+        return Err(invalid("index width does not match the index type"))
+    }
+    let indptr_len = nrows.checked_add(1).and_then(|n| n.checked_mul(8));
+    let indices_len = nnz.checked_mul(width);
+    let start = HEADER_LEN + indptr_len.ok_or(invalid("file length does not match its header"))?;
+    let end = indices_len.and_then(|n| n.checked_add(start));
+    if end.and_then(|n| n.checked_add(4)) != Some(bytes.len()) {
+        return Err(invalid("file length does not match its header"))
+    }
+    let (indptr, indices) = (&bytes[HEADER_LEN..start], &bytes[start..start + nnz * width]);
+    if !indptr.as_ptr().cast::<usize>().is_aligned() || !indices.as_ptr().cast::<T>().is_aligned() {
+        return Err(invalid("arrays are not aligned in memory"))
+    }
+    // SAFETY: both ranges lie within `bytes`, are suitably aligned, and hold whole
+    // elements. usize is a little-endian u64 here, and every bit pattern is a valid
+    // primitive integer, so the bytes can be read in place.
+    let (indptr, indices) = unsafe {
+        (
+            std::slice::from_raw_parts(indptr.as_ptr() as *const usize, nrows + 1),
+            std::slice::from_raw_parts(indices.as_ptr() as *const T, nnz),
+        )
+    };
+    Ok(GraphMatrixView {indptr, indices, ncols})
+}
+
+impl<'a, T> GraphMatrixView<'a, T> where T: PlainIndex {
+
+    /// Creates a view directly over a file written by `GraphMatrix::write_to`, such as a
+    /// memory-mapped one, without copying. Checks the header, checksum and CSR structure.
+    /// The indices must have been written at the width of `T`, and the buffer must be
+    /// aligned for `usize` (memory maps are page-aligned). Only 64-bit little-endian
+    /// targets are supported.
+    pub fn from_bytes(bytes: &'a [u8]) -> Result<Self, GraphMatrixError> {
+        let view = split(bytes)?;
+        let (body, crc) = bytes.split_at(bytes.len() - 4);
+        if crc32fast::hash(body).to_le_bytes() != crc {
+            return Err(invalid("checksum mismatch"))
+        }
+        validate_csr(view.indptr, view.indices, view.ncols)?;
+        Ok(view)
+    }
+
+    /// Like `from_bytes`, but checks only the header and layout, skipping the passes over
+    /// the whole file.
+    ///
+    /// # Safety
+    ///
+    /// The file must hold a valid matrix, as written by `GraphMatrix::write_to`.
+    pub unsafe fn from_bytes_unchecked(bytes: &'a [u8]) -> Result<Self, GraphMatrixError> {
+        split(bytes)
+    }
+}
+
+/// A GraphMatrix file mapped into memory, which the operating system pages in on demand and
+/// shares between processes.
+#[cfg(feature = "mmap")]
+#[derive(Debug)]
+pub struct MappedGraphMatrix<T> {
+    map: memmap2::Mmap,
+    marker: std::marker::PhantomData<T>,
+}
+
+#[cfg(feature = "mmap")]
+impl<T> MappedGraphMatrix<T> where T: PlainIndex {
+
+    /// Maps the file at `path`, checking it as `GraphMatrixView::from_bytes` does.
+    ///
+    /// # Safety
+    ///
+    /// The file must not be modified or truncated while it is mapped.
+    pub unsafe fn open<P: AsRef<std::path::Path>>(path: P) -> Result<Self, GraphMatrixError> {
+        let file = std::fs::File::open(path)?;
+        let map = memmap2::Mmap::map(&file)?;
+        GraphMatrixView::<T>::from_bytes(&map)?;
+        Ok(MappedGraphMatrix {map, marker: std::marker::PhantomData})
+    }
+
+    /// Returns a view of the mapped matrix.
+    pub fn view(&self) -> GraphMatrixView<'_, T> {
+        split(&self.map).expect("the file was checked when it was opened")
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// A 3x3 matrix written out, with row 0 claiming to run to index 9 and the checksum
+    /// recomputed so that only the structural checks can catch it.
+    fn crafted() -> Vec<u8> {
+        let g = GraphMatrix::<u32>::from_edgelist(vec![(0, 1), (1, 2), (2, 0)]).unwrap();
+        let mut bytes = Vec::new();
+        g.write_to(&mut bytes).unwrap();
+        bytes[HEADER_LEN + 8..HEADER_LEN + 16].copy_from_slice(&9u64.to_le_bytes());
+        rechecksum(&mut bytes);
+        bytes
+    }
+
+    /// Recomputes the trailing CRC after the body has been tampered with.
+    fn rechecksum(bytes: &mut [u8]) {
+        let body = bytes.len() - 4;
+        let crc = crc32fast::hash(&bytes[..body]);
+        bytes[body..].copy_from_slice(&crc.to_le_bytes());
+    }
+
+    fn corrupt(row: usize) -> GraphMatrixError {
+        let reason = "indptr points past the end of indices";
+        GraphMatrixError::CorruptStructure {row: Some(row), reason: reason.into()}
+    }
+
+    #[test]
+    fn round_trip() {
+        let g = GraphMatrix::<u16>::from_edgelist(vec![(0, 3), (2, 1), (3, 3)]).unwrap();
+        let mut bytes = Vec::new();
+        g.write_to(&mut bytes).unwrap();
+        let h = GraphMatrix::<u64>::read_from(bytes.as_slice()).unwrap();
+        assert_eq!(h.dims(), g.dims());
+        assert_eq!(h.iter().collect::<Vec<_>>(), vec![(0, 3), (2, 1), (3, 3)]);
+    }
+
+    #[test]
+    fn large_matrices_round_trip_across_widths() {
+        // more elements than one chunk, so encoding and decoding run several times.
+        let edges: Vec<(u32, u32)> = (0..100_000).map(|i| (i % 1000, i / 100 % 1000)).collect();
+        let g = GraphMatrix::from_edgelist(edges).unwrap();
+        let mut bytes = Vec::new();
+        g.write_to(&mut bytes).unwrap();
+        assert_eq!(bytes.len(), HEADER_LEN + 8 * 1001 + 4 * g.ne() + 4);
+        let h = GraphMatrix::<u16>::read_from(bytes.as_slice()).unwrap();
+        assert_eq!(h.indptr(), g.indptr());
+        assert!(h.indices().iter().zip(g.indices()).all(|(&a, &b)| u32::from(a) == b));
+        let err = GraphMatrix::<u8>::read_from(bytes.as_slice()).unwrap_err();
+        let first = g.indices().iter().find(|&&c| c > 255).unwrap();
+        assert_eq!(err, GraphMatrixError::Overflow {value: *first as usize});
+    }
+
+    #[test]
+    fn bad_headers_and_truncation() {
+        let g = GraphMatrix::<u32>::from_edgelist(vec![(0, 1), (1, 0)]).unwrap();
+        let mut good = Vec::new();
+        g.write_to(&mut good).unwrap();
+        let cases = [
+            (0, b'X', "not a GraphMatrix file"),
+            (8, 2, "unsupported version"),
+            (12, 3, "index width must be 1, 2, 4, 8 or 16 bytes"),
+        ];
+        for (at, byte, reason) in cases {
+            let mut bytes = good.clone();
+            bytes[at] = byte;
+            let err = GraphMatrix::<u32>::read_from(bytes.as_slice()).unwrap_err();
+            assert_eq!(err, invalid(reason));
+        }
+        for len in [10, HEADER_LEN + 3, good.len() - 1] {
+            let err = GraphMatrix::<u32>::read_from(&good[..len]).unwrap_err();
+            let eof = std::io::ErrorKind::UnexpectedEof;
+            assert!(matches!(err, GraphMatrixError::Io { kind, .. } if kind == eof));
+        }
+        let mut bytes = good.clone();
+        *bytes.last_mut().unwrap() ^= 1;
+        let err = GraphMatrix::<u32>::read_from(bytes.as_slice()).unwrap_err();
+        assert_eq!(err, invalid("checksum mismatch"));
+    }
+
+    /// Calls `f` with a copy of `bytes` in u64 storage, so the arrays are aligned as they are
+    /// in a memory map.
+    #[cfg(all(target_endian = "little", target_pointer_width = "64"))]
+    fn with_aligned<R>(bytes: &[u8], f: impl FnOnce(&[u8]) -> R) -> R {
+        let mut words = vec![0u64; bytes.len().div_ceil(8)];
+        // SAFETY: the words cover at least bytes.len() bytes, and any u8 is a valid value.
+        let aligned = unsafe {
+            std::slice::from_raw_parts_mut(words.as_mut_ptr() as *mut u8, bytes.len())
+        };
+        aligned.copy_from_slice(bytes);
+        f(aligned)
+    }
+
+    #[test]
+    #[cfg(all(target_endian = "little", target_pointer_width = "64"))]
+    fn views_over_bytes_match_the_matrix() {
+        let g = GraphMatrix::<u16>::from_edgelist(vec![(0, 3), (2, 1), (3, 3), (3, 4)]).unwrap();
+        let mut bytes = Vec::new();
+        g.write_to(&mut bytes).unwrap();
+        with_aligned(&bytes, |b| {
+            let view = GraphMatrixView::<u16>::from_bytes(b).unwrap();
+            assert_eq!(view.dims(), g.dims());
+            assert_eq!(view.indptr(), g.indptr());
+            assert_eq!(view.iter().collect::<Vec<_>>(), g.iter().collect::<Vec<_>>());
+            assert_eq!(view.row(3).unwrap(), &[3, 4]);
+            let unchecked = unsafe { GraphMatrixView::<u16>::from_bytes_unchecked(b) }.unwrap();
+            assert_eq!(unchecked.indices(), g.indices());
+            let err = GraphMatrixView::<u32>::from_bytes(b).unwrap_err();
+            assert_eq!(err, invalid("index width does not match the index type"));
+            let err = GraphMatrixView::<u16>::from_bytes(&b[..b.len() - 2]).unwrap_err();
+            assert_eq!(err, invalid("file length does not match its header"));
+        });
+        let mut shifted = vec![0];
+        shifted.extend_from_slice(&bytes);
+        let err = with_aligned(&shifted, |b| GraphMatrixView::<u16>::from_bytes(&b[1..]).err());
+        assert_eq!(err, Some(invalid("arrays are not aligned in memory")));
+        bytes[HEADER_LEN + 8] ^= 1;
+        let err = with_aligned(&bytes, |b| GraphMatrixView::<u16>::from_bytes(b).err());
+        assert_eq!(err, Some(invalid("checksum mismatch")));
+    }
+
+    #[test]
+    #[cfg(all(feature = "mmap", target_endian = "little", target_pointer_width = "64"))]
+    fn mapped_files_match_the_matrix() {
+        let path = std::env::temp_dir().join(format!("graphmatrix-{}.bin", std::process::id()));
+        let g = GraphMatrix::<u32>::from_edgelist(vec![(0, 1), (1, 2), (2, 0), (2, 2)]).unwrap();
+        let mut bytes = Vec::new();
+        g.write_to(&mut bytes).unwrap();
+        std::fs::write(&path, &bytes).unwrap();
+        let mapped = unsafe { MappedGraphMatrix::<u32>::open(&path) }.unwrap();
+        assert_eq!(mapped.view().iter().collect::<Vec<_>>(), g.iter().collect::<Vec<_>>());
+        assert_eq!(mapped.view().to_graph_matrix().indptr(), g.indptr());
+        drop(mapped);
+        bytes[HEADER_LEN + 8 * 4] ^= 2;
+        std::fs::write(&path, &bytes).unwrap();
+        let err = unsafe { MappedGraphMatrix::<u32>::open(&path) }.unwrap_err();
+        std::fs::remove_file(&path).unwrap();
+        assert_eq!(err, invalid("checksum mismatch"));
+    }
+
+    #[test]
+    fn read_from_rejects_checksum_mismatch() {
+        let mut bytes = crafted();
+        bytes[HEADER_LEN] ^= 1;
+        let err = GraphMatrix::<u32>::read_from(bytes.as_slice()).unwrap_err();
+        assert_eq!(err, invalid("checksum mismatch"));
+    }
+
+    #[test]
+    fn reserved_bytes_must_be_zero() {
+        let g = GraphMatrix::<u32>::from_edgelist(vec![(0, 1)]).unwrap();
+        let mut bytes = Vec::new();
+        g.write_to(&mut bytes).unwrap();
+        assert_eq!(bytes[13..16], [0, 0, 0]);
+        bytes[14] = 1;
+        rechecksum(&mut bytes);
+        let err = GraphMatrix::<u32>::read_from(bytes.as_slice()).unwrap_err();
+        assert_eq!(err, invalid("reserved header bytes are not zero"));
+    }
+
+    #[test]
+    fn read_from_rejects_crafted_structure() {
+        let err = GraphMatrix::<u32>::read_from(crafted().as_slice()).unwrap_err();
+        assert_eq!(err, corrupt(0));
+    }
+
+    #[test]
+    #[cfg(all(target_endian = "little", target_pointer_width = "64"))]
+    fn from_bytes_rejects_crafted_structure() {
+        let bytes = crafted();
+        // copy into u64 storage so the arrays are aligned, as they are in a memory map.
+        let mut words = vec![0u64; bytes.len().div_ceil(8)];
+        let aligned = unsafe {
+            std::slice::from_raw_parts_mut(words.as_mut_ptr() as *mut u8, bytes.len())
+        };
+        aligned.copy_from_slice(&bytes);
+        let err = GraphMatrixView::<u32>::from_bytes(aligned).unwrap_err();
+        assert_eq!(err, corrupt(0));
+    }
+}
@@ -0,0 +1,172 @@
+use std::io::BufRead;
+use std::ops::Add;
+use std::str::FromStr;
+
+use num::{One, PrimInt};
+
+use super::{parse_error, Lines};
+use crate::{DuplicatePolicy, GraphMatrix, GraphMatrixError, ValuedGraphMatrix};
+
+/// Options for reading a plain-text edge list, one `source target [weight]` edge per line.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct EdgeListConfig {
+    /// The character separating fields, such as `','` for CSV, or `None` to split on runs of
+    /// whitespace as in SNAP files.
+    pub delimiter: Option<char>,
+    /// Lines starting with this character are skipped.
+    pub comment: char,
+    /// Whether vertex ids count from one rather than zero.
+    pub one_based: bool,
+}
+
+impl Default for EdgeListConfig {
+    /// The SNAP conventions: whitespace-separated, `#` comments and 0-based ids.
+    fn default() -> Self {
+        EdgeListConfig {delimiter: None, comment: '#', one_based: false}
+    }
+}
+
+/// Parses a vertex id and shifts it to be 0-based.
+fn vertex<T: PrimInt>(
+    field: &str,
+    config: &EdgeListConfig,
+    line: usize,
+    edge: usize,
+) -> Result<(T, usize), GraphMatrixError> {
+    let raw: i128 = field.parse().map_err(|_| parse_error(line, "malformed vertex id"))?;
+    let index = if config.one_based { raw.saturating_sub(1) } else { raw };
+    let invalid = || GraphMatrixError::InvalidIndex {index, edge: Some(edge), line: Some(line)};
+    let v = usize::try_from(index).map_err(|_| invalid())?;
+    Ok((T::from(v).ok_or_else(invalid)?, v))
+}
+
+/// Streams the edges of `reader` into `push`, which gets the two endpoints and the weight
+/// field, if present. Returns the number of vertices: one more than the largest id.
+fn read_edges<T, R, F>(
+    reader: R,
+    config: &EdgeListConfig,
+    mut push: F,
+) -> Result<usize, GraphMatrixError>
+where
+    T: PrimInt,
+    R: BufRead,
+    F: FnMut(T, T, Option<&str>, usize) -> Result<(), GraphMatrixError>,
+{
+    let delimiter = config.delimiter;
+    let mut lines = Lines::new(reader, config.comment);
+    let mut n: Option<usize> = None;
+    let mut edge = 0;
+    while let Some((line, text)) = lines.next_line(false)? {
+        let mut fields = text
+            .split(|c: char| delimiter.map_or(c.is_whitespace(), |d| c == d))
+            .map(str::trim)
+            .filter(|f| delimiter.is_some() || !f.is_empty());
+        let (Some(s), Some(d)) = (fields.next(), fields.next()) else {
+            return Err(parse_error(line, "expected two vertex ids"))
+        };
+        let (ts, su) = vertex(s, config, line, edge)?;
+        let (td, du) = vertex(d, config, line, edge)?;
+        push(ts, td, fields.next(), line)?;
+        n = n.max(Some(su.max(du) + 1));
+        edge += 1;
+    }
+    n.ok_or(GraphMatrixError::EmptyInput)
+}
+
+/// Reads a plain-text edge list into a square GraphMatrix just large enough to hold the
+/// largest id, ignoring any weight column. Lines are parsed as they are read, and malformed
+/// ones are reported with their line number, as is an id that becomes negative or does not
+/// fit in `T`.
+pub fn read_edge_list<T, R>(
+    reader: R,
+    config: &EdgeListConfig,
+) -> Result<GraphMatrix<T>, GraphMatrixError>
+where
+    T: PrimInt,
+    R: BufRead,
+{
+    let mut edges = Vec::new();
+    let n = read_edges(reader, config, |s, d, _, _| {
+        edges.push((s, d));
+        Ok(())
+    })?;
+    GraphMatrix::from_edgelist_with_dims(edges, n, n)
+}
+
+/// Reads a plain-text edge list with an optional third column of weights, parsed as `V`,
+/// into a ValuedGraphMatrix. Edges without a weight get the value one, and repeated edges are
+/// merged according to `policy`.
+pub fn read_edge_list_valued<T, V, R>(
+    reader: R,
+    config: &EdgeListConfig,
+    policy: DuplicatePolicy,
+) -> Result<ValuedGraphMatrix<T, V>, GraphMatrixError>
+where
+    T: PrimInt,
+    V: PartialOrd + Add<Output = V> + One + FromStr,
+    R: BufRead,
+{
+    let mut edges = Vec::new();
+    let n = read_edges(reader, config, |s, d, w, line| {
+        let w = match w {
+            None => V::one(),
+            Some(w) => w.parse().map_err(|_| parse_error(line, "malformed weight"))?,
+        };
+        edges.push((s, d, w));
+        Ok(())
+    })?;
+    ValuedGraphMatrix::from_edgelist_with_dims(edges, n, n, policy)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn reads_snap_and_csv() {
+        let snap = "# comment\n0 2\n\n2\t1\n";
+        let g = read_edge_list::<u32, _>(snap.as_bytes(), &EdgeListConfig::default()).unwrap();
+        assert_eq!(g.iter().collect::<Vec<_>>(), vec![(0, 2), (2, 1)]);
+        let config = EdgeListConfig {delimiter: Some(','), one_based: true, ..Default::default()};
+        let g = read_edge_list::<u32, _>("1, 3\n".as_bytes(), &config).unwrap();
+        assert_eq!(g.iter().collect::<Vec<_>>(), vec![(0, 2)]);
+    }
+
+    #[test]
+    fn weights_default_to_one_and_merge() {
+        let text = "0 1 2.5\n1 2\n0 1 0.5\n";
+        let config = EdgeListConfig::default();
+        let g = read_edge_list_valued::<u32, f64, _>(text.as_bytes(), &config, DuplicatePolicy::Sum)
+            .unwrap();
+        assert_eq!(g.graph().iter().collect::<Vec<_>>(), vec![(0, 1), (1, 2)]);
+        assert_eq!(g.values(), &[3.0, 1.0]);
+        let policy = DuplicatePolicy::Max;
+        let g = read_edge_list_valued::<u32, f64, _>(text.as_bytes(), &config, policy).unwrap();
+        assert_eq!(g.values(), &[2.5, 1.0]);
+    }
+
+    #[test]
+    fn malformed_lines_carry_their_line() {
+        let config = EdgeListConfig::default();
+        let policy = DuplicatePolicy::Sum;
+        for (text, line) in [("0 1\n2\n", 2), ("# c\n0 x\n", 2), ("0 1\n\n\n1 2 x\n", 4)] {
+            let err = read_edge_list_valued::<u32, u32, _>(text.as_bytes(), &config, policy);
+            let err = err.unwrap_err();
+            assert!(matches!(err, GraphMatrixError::Parse { line: l, .. } if l == line), "{}", err);
+        }
+        let config = EdgeListConfig {one_based: true, ..config};
+        let err = read_edge_list::<u32, _>("1 2\n2 0\n".as_bytes(), &config).unwrap_err();
+        assert_eq!(err, GraphMatrixError::InvalidIndex {index: -1, edge: Some(1), line: Some(2)});
+        let err = read_edge_list::<u32, _>("# nothing\n".as_bytes(), &config).unwrap_err();
+        assert_eq!(err, GraphMatrixError::EmptyInput);
+    }
+
+    #[test]
+    fn invalid_ids_carry_their_line() {
+        let config = EdgeListConfig::default();
+        let err = read_edge_list::<u8, _>("0 1\n# c\n300 1\n".as_bytes(), &config).unwrap_err();
+        assert_eq!(err, GraphMatrixError::InvalidIndex {index: 300, edge: Some(1), line: Some(3)});
+        let err = read_edge_list::<u8, _>("0 -1\n".as_bytes(), &config).unwrap_err();
+        assert_eq!(err, GraphMatrixError::InvalidIndex {index: -1, edge: Some(0), line: Some(1)});
+    }
+}
@@ -0,0 +1,377 @@
+use std::fmt::Display;
+use std::io::{BufRead, Write};
+use std::ops::Add;
+use std::str::FromStr;
+
+use num::{One, PrimInt};
+
+use super::{parse_error, Lines};
+use crate::{
+    check_dim_fits, from_usize, index, DuplicatePolicy, GraphMatrix, GraphMatrixError,
+    ValuedGraphMatrix,
+};
+
+/// The most entries reserved up front, however many the size line declares.
+const MAX_PREALLOC: usize = 1 << 20;
+
+/// The type of the values stored with each entry of a Matrix Market file.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Field {
+    /// No values: only the positions of the entries.
+    Pattern,
+    Real,
+    Integer,
+}
+
+/// How a Matrix Market file stores its entries.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Symmetry {
+    /// Every entry is listed.
+    General,
+    /// Only the lower triangle is listed, and each off-diagonal entry `(i, j)` stands for
+    /// `(j, i)` as well.
+    Symmetric,
+}
+
+/// Parses the `%%MatrixMarket` banner on the first line.
+fn parse_banner(line: Option<&str>) -> Result<(Field, Symmetry), GraphMatrixError> {
+    let err = |reason| parse_error(1, reason);
+    let line = line.ok_or(err("missing %%MatrixMarket banner"))?.to_ascii_lowercase();
+    let words: Vec<&str> = line.split_whitespace().collect();
+    if words.len() != 5 || words[0] != "%%matrixmarket" || words[1] != "matrix" {
+        return Err(err("missing %%MatrixMarket banner"))
+    }
+    if words[2] != "coordinate" {
+        return Err(err("only the coordinate format is supported"))
+    }
+    let field = match words[3] {
+        "pattern" => Field::Pattern,
+        "real" | "double" => Field::Real,
+        "integer" => Field::Integer,
+        _ => return Err(err("only pattern, real and integer fields are supported")),
+    };
+    let symmetry = match words[4] {
+        "general" => Symmetry::General,
+        "symmetric" => Symmetry::Symmetric,
+        _ => return Err(err("only general and symmetric matrices are supported")),
+    };
+    Ok((field, symmetry))
+}
+
+/// The entries of a Matrix Market file, converted to 0-based indices, with symmetric
+/// entries mirrored.
+struct Entries<T, V> {
+    nrows: usize,
+    ncols: usize,
+    edges: Vec<(T, T, V)>,
+}
+
+/// Reads a whole Matrix Market file. `value` turns the value column (absent for pattern
+/// files) into a `V`, or returns `None` if it is malformed.
+fn read_entries<T, V, R, F>(reader: R, mut value: F) -> Result<Entries<T, V>, GraphMatrixError>
+where
+    T: PrimInt,
+    V: Clone,
+    R: BufRead,
+    F: FnMut(Option<&str>) -> Option<V>,
+{
+    let mut lines = Lines::new(reader, '%');
+    let (field, symmetry) = parse_banner(lines.next_line(true)?.map(|(_, text)| text))?;
+
+    let Some((line, size)) = lines.next_line(false)? else {
+        return Err(parse_error(lines.line + 1, "missing size line"))
+    };
+    let size: Vec<usize> = size
+        .split_whitespace()
+        .map(|w| w.parse().map_err(|_| parse_error(line, "malformed size line")))
+        .collect::<Result<_, _>>()?;
+    let [nrows, ncols, nnz] = size[..] else {
+        return Err(parse_error(line, "size line must hold rows, columns and entries"))
+    };
+    check_dim_fits::<T>(nrows)?;
+    check_dim_fits::<T>(ncols)?;
+    if symmetry == Symmetry::Symmetric && nrows != ncols {
+        return Err(parse_error(line, "a symmetric matrix must be square"))
+    }
+
+    // nnz comes from the file, so trust it only for a bounded head start.
+    let mut edges = Vec::with_capacity(nnz.min(MAX_PREALLOC));
+    let mut read = 0;
+    while let Some((line, text)) = lines.next_line(false)? {
+        if read == nnz {
+            return Err(parse_error(line, "more entries than the size line declares"))
+        }
+        read += 1;
+        let mut words = text.split_whitespace();
+        let mut index = |bound: usize| -> Result<usize, GraphMatrixError> {
+            let i: usize = words
+                .next()
+                .and_then(|w| w.parse().ok())
+                .ok_or(parse_error(line, "malformed index"))?;
+            if i == 0 || i > bound {
+                return Err(parse_error(line, "index out of range"))
+            }
+            Ok(i - 1)
+        };
+        let (r, c) = (index(nrows)?, index(ncols)?);
+        let v = match field {
+            Field::Pattern => value(None),
+            _ => value(Some(words.next().ok_or(parse_error(line, "missing value"))?)),
+        };
+        let v = v.ok_or(parse_error(line, "malformed value"))?;
+        let (tr, tc) = (from_usize(r)?, from_usize(c)?);
+        if symmetry == Symmetry::Symmetric && r != c {
+            edges.push((tc, tr, v.clone()));
+        }
+        edges.push((tr, tc, v));
+    }
+    if read < nnz {
+        return Err(parse_error(lines.line, "fewer entries than the size line declares"))
+    }
+    Ok(Entries {nrows, ncols, edges})
+}
+
+/// Reads the structure of a Matrix Market coordinate file into a GraphMatrix, ignoring any
+/// values. Indices are converted from 1-based to 0-based, and the entries of a symmetric
+/// file are mirrored.
+pub fn read_matrix_market<T, R>(reader: R) -> Result<GraphMatrix<T>, GraphMatrixError>
+where
+    T: PrimInt,
+    R: BufRead,
+{
+    let Entries {nrows, ncols, edges} = read_entries(reader, |_| Some(()))?;
+    let edgelist = edges.into_iter().map(|(r, c, _)| (r, c)).collect();
+    GraphMatrix::from_edgelist_with_dims(edgelist, nrows, ncols)
+}
+
+/// Reads a Matrix Market coordinate file into a ValuedGraphMatrix. Values are parsed as `V`,
+/// and pattern files give every entry the value one. Repeated entries are summed.
+pub fn read_matrix_market_valued<T, V, R>(
+    reader: R,
+) -> Result<ValuedGraphMatrix<T, V>, GraphMatrixError>
+where
+    T: PrimInt,
+    V: Clone + PartialOrd + Add<Output = V> + One + FromStr,
+    R: BufRead,
+{
+    let Entries {nrows, ncols, edges} = read_entries(reader, |w| match w {
+        None => Some(V::one()),
+        Some(w) => w.parse().ok(),
+    })?;
+    ValuedGraphMatrix::from_edgelist_with_dims(edges, nrows, ncols, DuplicatePolicy::Sum)
+}
+
+/// Writes the header and the entries that `symmetry` keeps: all of them for a general
+/// matrix, or the lower triangle for a symmetric one. `value` formats the value column for
+/// the entry at position `i`, or returns `None` to write positions only.
+fn write_entries<T, W, F>(
+    g: &GraphMatrix<T>,
+    field: Field,
+    symmetry: Symmetry,
+    mut writer: W,
+    value: F,
+) -> Result<(), GraphMatrixError>
+where
+    T: PrimInt,
+    W: Write,
+    F: Fn(usize) -> Option<String>,
+{
+    let field_name = match field {
+        Field::Pattern => "pattern",
+        Field::Real => "real",
+        Field::Integer => "integer",
+    };
+    let symmetry_name = match symmetry {
+        Symmetry::General => "general",
+        Symmetry::Symmetric => "symmetric",
+    };
+    let keep = |r: usize, c: usize| symmetry == Symmetry::General || r >= c;
+    let (nrows, ncols) = g.dims();
+    let nnz = g.iter().filter(|&(r, c)| keep(index(r), index(c))).count();
+    writeln!(writer, "%%MatrixMarket matrix coordinate {} {}", field_name, symmetry_name)?;
+    writeln!(writer, "{} {} {}", nrows, ncols, nnz)?;
+    for (i, (r, c)) in g.iter().enumerate() {
+        let (r, c) = (index(r), index(c));
+        if !keep(r, c) {
+            continue;
+        }
+        match value(i) {
+            Some(v) => writeln!(writer, "{} {} {}", r + 1, c + 1, v)?,
+            None => writeln!(writer, "{} {}", r + 1, c + 1)?,
+        }
+    }
+    Ok(())
+}
+
+fn not_symmetric() -> GraphMatrixError {
+    GraphMatrixError::invalid_parameter("matrix is not symmetric")
+}
+
+/// Writes the structure of `g` as a Matrix Market pattern file. With
+/// `Symmetry::Symmetric`, only the lower triangle is written, and
+/// `GraphMatrixError::InvalidParameter` is returned if `g` is not symmetric.
+pub fn write_matrix_market<T, W>(
+    g: &GraphMatrix<T>,
+    symmetry: Symmetry,
+    writer: W,
+) -> Result<(), GraphMatrixError>
+where
+    T: PrimInt,
+    W: Write,
+{
+    if symmetry == Symmetry::Symmetric {
+        for (r, c) in g.iter() {
+            if !g.has_index(c, r).unwrap_or(false) {
+                return Err(not_symmetric())
+            }
+        }
+    }
+    write_entries(g, Field::Pattern, symmetry, writer, |_| None)
+}
+
+/// Checks that a formatted value is valid for `field`: an integer for `Field::Integer`, or a
+/// finite number for `Field::Real`.
+fn check_value(field: Field, text: &str) -> Result<(), GraphMatrixError> {
+    match field {
+        Field::Pattern => Ok(()),
+        Field::Integer if text.parse::<i128>().is_err() => {
+            Err(GraphMatrixError::invalid_parameter("an integer field needs integer values"))
+        }
+        Field::Real if !text.parse::<f64>().is_ok_and(f64::is_finite) => {
+            Err(GraphMatrixError::invalid_parameter("a real field needs finite values"))
+        }
+        _ => Ok(()),
+    }
+}
+
+/// Writes `g` as a Matrix Market file with the given field, formatting values with
+/// `Display`. `Field::Pattern` drops the values. `GraphMatrixError::InvalidParameter` is
+/// returned, before anything is written, if a value does not format as an integer for
+/// `Field::Integer` or as a finite number for `Field::Real`. With `Symmetry::Symmetric`,
+/// only the lower triangle is written, and the same error is returned unless `g` is
+/// symmetric in both structure and values.
+pub fn write_matrix_market_valued<T, V, W>(
+    g: &ValuedGraphMatrix<T, V>,
+    field: Field,
+    symmetry: Symmetry,
+    writer: W,
+) -> Result<(), GraphMatrixError>
+where
+    T: PrimInt,
+    V: Display + PartialEq,
+    W: Write,
+{
+    let values = g.values();
+    if symmetry == Symmetry::Symmetric {
+        for ((r, c), v) in g.graph().iter().zip(values) {
+            if g.get(c, r) != Some(v) {
+                return Err(not_symmetric())
+            }
+        }
+    }
+    if field != Field::Pattern {
+        for v in values {
+            check_value(field, &v.to_string())?;
+        }
+    }
+    let value = |i: usize| (field != Field::Pattern).then(|| values[i].to_string());
+    write_entries(g.graph(), field, symmetry, writer, value)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn round_trip_symmetric() {
+        let text = "%%MatrixMarket matrix coordinate pattern symmetric\n3 3 2\n2 1\n3 3\n";
+        let g = read_matrix_market::<u32, _>(text.as_bytes()).unwrap();
+        assert_eq!(g.iter().collect::<Vec<_>>(), vec![(0, 1), (1, 0), (2, 2)]);
+        let mut out = Vec::new();
+        write_matrix_market(&g, Symmetry::Symmetric, &mut out).unwrap();
+        assert_eq!(String::from_utf8(out).unwrap(), text);
+    }
+
+    #[test]
+    fn valued_round_trip() {
+        let edges = vec![(0u32, 3u32, 0.5), (2, 1, -2.25), (2, 3, 4.0), (1, 0, 1e-3)];
+        let g = ValuedGraphMatrix::from_edgelist_with_dims(edges, 3, 5, DuplicatePolicy::Sum)
+            .unwrap();
+        let mut out = Vec::new();
+        write_matrix_market_valued(&g, Field::Real, Symmetry::General, &mut out).unwrap();
+        let h = read_matrix_market_valued::<u32, f64, _>(&out[..]).unwrap();
+        assert_eq!(h.graph().dims(), (3, 5));
+        assert_eq!(h.graph().iter().collect::<Vec<_>>(), g.graph().iter().collect::<Vec<_>>());
+        assert_eq!(h.values(), g.values());
+    }
+
+    #[test]
+    fn reads_comments_duplicates_and_patterns() {
+        let text = "%%MatrixMarket matrix coordinate integer general\n% a comment\n\n\
+                    2 3 3\n1 3 4\n% another\n1 3 5\n2 1 -1\n";
+        let g = read_matrix_market_valued::<u32, i64, _>(text.as_bytes()).unwrap();
+        assert_eq!(g.graph().dims(), (2, 3));
+        assert_eq!(g.graph().iter().collect::<Vec<_>>(), vec![(0, 2), (1, 0)]);
+        assert_eq!(g.values(), &[9, -1]);
+        let text = "%%MatrixMarket matrix coordinate pattern general\n2 2 2\n1 2\n2 2\n";
+        let g = read_matrix_market_valued::<u32, u8, _>(text.as_bytes()).unwrap();
+        assert_eq!(g.values(), &[1, 1]);
+    }
+
+    #[test]
+    fn errors_carry_their_line() {
+        let banner = "%%MatrixMarket matrix coordinate real general\n";
+        let cases = [
+            ("%%MatrixMarket matrix array real general\n2 2\n".to_string(), 1),
+            (format!("{}% size next\n2 two 1\n", banner), 3),
+            (format!("{}2 2 1\n3 1 1.0\n", banner), 3),
+            (format!("{}2 2 1\n1 1 x\n", banner), 3),
+            (format!("{}2 2 2\n1 1 1.0\n", banner), 3),
+            (format!("{}2 2 1\n1 1 1.0\n\n2 2 2.0\n", banner), 5),
+            ("%%MatrixMarket matrix coordinate real symmetric\n2 3 0\n".to_string(), 2),
+        ];
+        for (text, line) in cases {
+            let err = read_matrix_market_valued::<u32, f64, _>(text.as_bytes()).unwrap_err();
+            assert!(matches!(err, GraphMatrixError::Parse { line: l, .. } if l == line), "{}", err);
+        }
+    }
+
+    #[test]
+    fn symmetric_writes_need_a_symmetric_matrix() {
+        let g = GraphMatrix::<u32>::from_edgelist(vec![(0, 1)]).unwrap();
+        let err = write_matrix_market(&g, Symmetry::Symmetric, Vec::new());
+        assert!(matches!(err, Err(GraphMatrixError::InvalidParameter { .. })));
+        let edges = vec![(0u32, 1u32, 1), (1, 0, 2)];
+        let g = ValuedGraphMatrix::from_edgelist(edges, DuplicatePolicy::Sum).unwrap();
+        let err = write_matrix_market_valued(&g, Field::Integer, Symmetry::Symmetric, Vec::new());
+        assert!(matches!(err, Err(GraphMatrixError::InvalidParameter { .. })));
+    }
+
+    #[test]
+    fn values_must_suit_the_field() {
+        let edges = vec![(0u32, 1u32, 1.5), (1, 0, f64::NAN)];
+        let g = ValuedGraphMatrix::from_edgelist(edges, DuplicatePolicy::Sum).unwrap();
+        for field in [Field::Integer, Field::Real] {
+            let mut out = Vec::new();
+            let err = write_matrix_market_valued(&g, field, Symmetry::General, &mut out);
+            assert!(matches!(err, Err(GraphMatrixError::InvalidParameter { .. })));
+            assert!(out.is_empty());
+        }
+        let edges = vec![(0u32, 1u32, 2.0), (1, 0, -3.0)];
+        let g = ValuedGraphMatrix::from_edgelist(edges, DuplicatePolicy::Sum).unwrap();
+        let mut out = Vec::new();
+        write_matrix_market_valued(&g, Field::Integer, Symmetry::General, &mut out).unwrap();
+        let text = String::from_utf8(out).unwrap();
+        let expected = "%%MatrixMarket matrix coordinate integer general\n2 2 2\n1 2 2\n2 1 -3\n";
+        assert_eq!(text, expected);
+        let h = read_matrix_market_valued::<u32, i64, _>(text.as_bytes()).unwrap();
+        assert_eq!(h.values(), &[2, -3]);
+    }
+
+    #[test]
+    fn oversized_entry_count() {
+        let text = "%%MatrixMarket matrix coordinate pattern general\n2 2 18446744073709551615\n";
+        let err = read_matrix_market::<u32, _>(text.as_bytes()).unwrap_err();
+        assert_eq!(err, parse_error(2, "fewer entries than the size line declares"));
+    }
+}
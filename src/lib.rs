@@ -1,36 +1,158 @@
 use num::PrimInt;
 
-pub enum GraphMatrixError {
-    InvalidIndex,
-    BoundsError,
-}
+pub mod centrality;
+pub mod components;
+pub mod cores;
+pub mod dag;
+pub mod generators;
+pub mod io;
+pub mod pagerank;
+pub mod semiring;
+pub mod shortest_paths;
+pub mod spmv;
+pub mod traversal;
+pub mod triangles;
+
+mod bigraph;
+mod bitset;
+mod error;
+#[cfg(feature = "petgraph")]
+mod petgraph_impls;
+#[cfg(feature = "serde")]
+mod serde_impls;
+mod valued;
+mod view;
+
+pub use bigraph::BiGraphMatrix;
+pub use bitset::Bitset;
+pub use error::GraphMatrixError;
+pub use valued::{DuplicatePolicy, ValuedGraphMatrix};
+pub use view::{GraphMatrixView, PlainIndex};
+
+pub(crate) use error::{from_usize, index, to_usize};
+
+/// Row pointers, column indices and the values that go with them.
+pub(crate) type Compressed<T, V> = (Vec<usize>, Vec<T>, Vec<V>);
 
 /// Given identically-sized vectors representing row/column data, return a sparse matrix
-/// representation.
-fn compress<T: PrimInt>(row: Vec<T>, col: Vec<T>, n: usize) -> Result<(Vec<usize>, Vec<T>), GraphMatrixError> {
+/// representation. `vals` is permuted alongside `col`, so the returned values line up with
+/// the returned indices.
+pub(crate) fn compress<T: PrimInt, V>(
+    row: Vec<T>,
+    mut col: Vec<T>,
+    mut vals: Vec<V>,
+    n: usize,
+) -> Result<Compressed<T, V>, GraphMatrixError> {
 
     let mut w: Vec<usize> = vec![0; n];
-    let mut ja: Vec<T> = vec![T::zero(); col.len()];
-
     for v in &row {
-        w[v.to_usize().ok_or(GraphMatrixError::InvalidIndex)?] += 1;
+        w[to_usize(*v)?] += 1;
     }
     let ia = w.iter().fold(vec![0], |mut acc, val| {
         acc.push(val + acc.last().unwrap());
         acc
     });
+    // each element's destination is the next free slot in its row.
     let mut w = ia.clone();
-    if let Some(last) = w.last_mut() {
-        *last = 0;
-    }
-    for (j, v) in col.into_iter().enumerate() {
-        let rj = row[j].to_usize().ok_or(GraphMatrixError::InvalidIndex)?;
-        let p = w[rj];
-        ja[p] = v;
+    let mut dest: Vec<usize> = Vec::with_capacity(row.len());
+    for v in &row {
+        let rj = to_usize(*v)?;
+        dest.push(w[rj]);
         w[rj] += 1;
     }
+    // apply the permutation in place by following its cycles, so no values are copied.
+    for i in 0..dest.len() {
+        while dest[i] != i {
+            let j = dest[i];
+            col.swap(i, j);
+            vals.swap(i, j);
+            dest.swap(i, j);
+        }
+    }
+
+    Ok((ia, col, vals))
+}
+
+/// Validates `edges` and returns the dimensions of the matrix that holds them: `dims` if
+/// given, otherwise the smallest square matrix that fits every index. Errors refer to
+/// positions in `edges`, so call this before any reordering.
+pub(crate) fn edge_dims<T: PrimInt>(
+    edges: impl Iterator<Item = (T, T)>,
+    dims: Option<(usize, usize)>,
+) -> Result<(usize, usize), GraphMatrixError> {
+    let mut m: Option<usize> = None;
+    for (i, (s, d)) in edges.enumerate() {
+        let su = s.to_usize().ok_or_else(|| GraphMatrixError::invalid_index(s, Some(i)))?;
+        let du = d.to_usize().ok_or_else(|| GraphMatrixError::invalid_index(d, Some(i)))?;
+        if let Some((nrows, ncols)) = dims {
+            for (index, bound) in [(su, nrows), (du, ncols)] {
+                if index >= bound {
+                    return Err(GraphMatrixError::BoundsError {index, bound, edge: Some(i)})
+                }
+            }
+        }
+        m = m.max(Some(su.max(du)));
+    }
+    match dims {
+        Some((nrows, ncols)) => {
+            check_dim_fits::<T>(nrows)?;
+            check_dim_fits::<T>(ncols)?;
+            Ok((nrows, ncols))
+        }
+        None => m.map(|m| (m + 1, m + 1)).ok_or(GraphMatrixError::EmptyInput),
+    }
+}
+
+/// Checks that every index below `n` can be represented as a `T`.
+pub(crate) fn check_dim_fits<T: PrimInt>(n: usize) -> Result<(), GraphMatrixError> {
+    if n > 0 {
+        from_usize::<T>(n - 1)?;
+    }
+    Ok(())
+}
 
-    Ok((ia, ja))
+/// Checks the CSR invariants: `indptr` starts at zero, never decreases and ends at
+/// `indices.len()`, and each row's indices are strictly increasing and below `ncols`.
+pub(crate) fn validate_csr<T: PrimInt>(
+    indptr: &[usize],
+    indices: &[T],
+    ncols: usize,
+) -> Result<(), GraphMatrixError> {
+    let corrupt = |row, reason: &'static str| GraphMatrixError::CorruptStructure {
+        row,
+        reason: reason.into(),
+    };
+    match indptr.first() {
+        None => return Err(corrupt(None, "indptr is empty")),
+        Some(&p) if p != 0 => return Err(corrupt(None, "indptr does not start at zero")),
+        _ => {}
+    }
+    if indptr[indptr.len() - 1] != indices.len() {
+        return Err(corrupt(None, "last indptr entry does not match the number of indices"))
+    }
+    check_dim_fits::<T>(indptr.len() - 1)?;
+    check_dim_fits::<T>(ncols)?;
+    for (r, w) in indptr.windows(2).enumerate() {
+        if w[0] > w[1] {
+            return Err(corrupt(Some(r), "indptr is not monotone"))
+        }
+        // the windows are checked one at a time, so a pointer can overshoot before a later
+        // one is seen to decrease.
+        let Some(row) = indices.get(w[0]..w[1]) else {
+            return Err(corrupt(Some(r), "indptr points past the end of indices"))
+        };
+        if row.windows(2).any(|p| p[0] >= p[1]) {
+            return Err(corrupt(Some(r), "row indices are not sorted and unique"))
+        }
+        for (offset, &c) in row.iter().enumerate() {
+            let edge = Some(w[0] + offset);
+            let index = c.to_usize().ok_or_else(|| GraphMatrixError::invalid_index(c, edge))?;
+            if index >= ncols {
+                return Err(GraphMatrixError::BoundsError {index, bound: ncols, edge})
+            }
+        }
+    }
+    Ok(())
 }
 
 /// A GraphMatrix is a compressed sparse row matrix with no "value" vector. An element is 
@@ -39,79 +161,434 @@ fn compress<T: PrimInt>(row: Vec<T>, col: Vec<T>, n: usize) -> Result<(Vec<usize
 pub struct GraphMatrix<T> {
     indptr: Vec<usize>,
     indices: Vec<T>,
+    ncols: usize,
 }
 
 impl<T> GraphMatrix<T> where T: PrimInt {
 
+    /// Returns `(nrows, ncols)`.
     pub fn dims(&self) -> (usize, usize) {
-        (self.indptr.len() - 1, self.indptr.len() - 1)
+        (self.indptr.len() - 1, self.ncols)
     }
 
     pub fn ne(&self) -> usize {
         self.indices.len()
     }
 
-    pub fn row(&self, r: T) -> Result<&[T], GraphMatrixError> {
-        let ru = r.to_usize().ok_or(GraphMatrixError::InvalidIndex)?;
-        if ru > self.indptr.len() - 2 {
-            return Err(GraphMatrixError::BoundsError)
-        }
+    /// The row pointer array: row `r` occupies `indices()[indptr()[r]..indptr()[r + 1]]`.
+    pub fn indptr(&self) -> &[usize] {
+        &self.indptr
+    }
+
+    /// The column indices of every element, row by row.
+    pub fn indices(&self) -> &[T] {
+        &self.indices
+    }
+
+    /// Returns the positions in `indices` occupied by row `r`.
+    pub(crate) fn row_range(&self, r: T) -> Result<std::ops::Range<usize>, GraphMatrixError> {
+        let ru = self.vertex(r)?;
         let start_index = unsafe { self.indptr.get_unchecked(ru) };
         let end_index = unsafe { self.indptr.get_unchecked(ru+1) };
-        Ok(&self.indices[*start_index..*end_index])
+        Ok(*start_index..*end_index)
+    }
+
+    pub fn row(&self, r: T) -> Result<&[T], GraphMatrixError> {
+        self.view().row(r)
+    }
+
+    /// Row `r` by position. Panics if `r` is out of bounds.
+    pub(crate) fn row_slice(&self, r: usize) -> &[T] {
+        &self.indices[self.indptr[r]..self.indptr[r + 1]]
+    }
+
+    /// Row `r` by position, paired with the entries of `values` aligned with it.
+    pub(crate) fn row_with_values<'a, V>(
+        &'a self,
+        r: usize,
+        values: &'a [V],
+    ) -> impl Iterator<Item = (T, &'a V)> + 'a {
+        let range = self.indptr[r]..self.indptr[r + 1];
+        self.indices[range.clone()].iter().copied().zip(&values[range])
+    }
+
+    /// Returns the number of vertices if this matrix is square, as every graph algorithm
+    /// requires.
+    pub(crate) fn nv(&self) -> Result<usize, GraphMatrixError> {
+        let (nrows, ncols) = self.dims();
+        if nrows != ncols {
+            return Err(GraphMatrixError::DimensionMismatch {expected: nrows, found: ncols})
+        }
+        Ok(nrows)
+    }
+
+    /// The degree of every vertex, not counting self-loops. Requires a square matrix.
+    pub(crate) fn degrees_without_loops(&self) -> Result<Vec<usize>, GraphMatrixError> {
+        (0..self.nv()?)
+            .map(|u| {
+                let row = self.row_slice(u);
+                let has_loop = row.binary_search(&from_usize(u)?).is_ok();
+                Ok(row.len() - usize::from(has_loop))
+            })
+            .collect()
+    }
+
+    /// Converts vertex `v` to a position, checking it against the number of rows.
+    pub(crate) fn vertex(&self, v: T) -> Result<usize, GraphMatrixError> {
+        let vu = to_usize(v)?;
+        let nrows = self.indptr.len() - 1;
+        if vu >= nrows {
+            return Err(GraphMatrixError::BoundsError {index: vu, bound: nrows, edge: None})
+        }
+        Ok(vu)
     }
 
     pub fn row_len(&self, r: T) -> Result<usize, GraphMatrixError> {
-        Ok(self.row(r)?.len())
+        self.view().row_len(r)
     }
 
-    pub fn has_index(&self, r: T, c: T) -> Result<bool, GraphMatrixError>
-    {
-        let row = self.row(r)?;
-        let tc = T::from(c).ok_or(GraphMatrixError::InvalidIndex)?;
-        Ok(row.binary_search(&tc).is_ok())
+    pub fn has_index(&self, r: T, c: T) -> Result<bool, GraphMatrixError> {
+        self.view().has_index(r, c)
     }
 
+    /// Iterates over every `(row, col)` element in row-major order.
+    pub fn iter(&self) -> GraphMatrixIterator<'_, T> {
+        GraphMatrixIterator::new(self)
+    }
+
+    /// Borrows this matrix as a GraphMatrixView, so code written against views also works on
+    /// owned matrices.
+    pub fn view(&self) -> GraphMatrixView<'_, T> {
+        GraphMatrixView {indptr: &self.indptr, indices: &self.indices, ncols: self.ncols}
+    }
+
+    /// Returns the subgraph induced by the vertices set in `keep`, renumbered in increasing
+    /// order, together with the original id of each new vertex.
+    pub fn induced_subgraph(&self, keep: &Bitset) -> Result<(Self, Vec<T>), GraphMatrixError> {
+        let n = self.nv()?;
+        if keep.len() != n {
+            return Err(GraphMatrixError::DimensionMismatch {expected: n, found: keep.len()})
+        }
+        let mut new_id: Vec<Option<T>> = vec![None; n];
+        let mut old_ids: Vec<T> = Vec::with_capacity(keep.count_ones());
+        for v in keep.iter() {
+            new_id[v] = Some(from_usize(old_ids.len())?);
+            old_ids.push(from_usize(v)?);
+        }
+        let mut indptr: Vec<usize> = Vec::with_capacity(old_ids.len() + 1);
+        let mut indices: Vec<T> = Vec::new();
+        indptr.push(0);
+        for v in keep.iter() {
+            // renumbering preserves order, so each row stays sorted.
+            indices.extend(self.row_slice(v).iter().filter_map(|&c| new_id[c.to_usize()?]));
+            indptr.push(indices.len());
+        }
+        let ncols = old_ids.len();
+        Ok((GraphMatrix {indptr, indices, ncols}, old_ids))
+    }
+
+    /// Returns the transpose of this matrix, so that row `c` of the result lists every `r`
+    /// for which `(r, c)` exists here. Runs in O(V + E).
+    pub fn transpose(&self) -> Result<Self, GraphMatrixError> {
+        let (nrows, ncols) = self.dims();
+        let mut rows: Vec<T> = Vec::with_capacity(self.ne());
+        for (r, w) in self.indptr.windows(2).enumerate() {
+            let tr: T = from_usize(r)?;
+            rows.extend(std::iter::repeat_n(tr, w[1] - w[0]));
+        }
+        let vals = vec![(); rows.len()];
+        let (indptr, indices, _) = compress(self.indices.clone(), rows, vals, ncols)?;
+        Ok(GraphMatrix {indptr, indices, ncols: nrows})
+    }
+
+    /// Builds a square GraphMatrix just large enough to hold the largest index in `edgelist`.
     pub fn from_edgelist(edgelist: Vec<(T, T)>) -> Result<Self, GraphMatrixError> {
+        let (nrows, ncols) = edge_dims(edgelist.iter().copied(), None)?;
+        let (ss, ds) = Self::sorted_edges(edgelist);
+        Self::from_sorted_edges(ss, ds, nrows, ncols)
+    }
+
+    /// Builds an `nrows` x `ncols` GraphMatrix. Returns `GraphMatrixError::BoundsError` if any
+    /// index in `edgelist` falls outside those dimensions.
+    pub fn from_edgelist_with_dims(
+        edgelist: Vec<(T, T)>,
+        nrows: usize,
+        ncols: usize,
+    ) -> Result<Self, GraphMatrixError> {
+        edge_dims(edgelist.iter().copied(), Some((nrows, ncols)))?;
+        let (ss, ds) = Self::sorted_edges(edgelist);
+        Self::from_sorted_edges(ss, ds, nrows, ncols)
+    }
+
+    /// Builds a square GraphMatrix from existing CSR arrays, checking that they are
+    /// well-formed. The number of columns is taken to be the number of rows.
+    pub fn try_from_csr(indptr: Vec<usize>, indices: Vec<T>) -> Result<Self, GraphMatrixError> {
+        let ncols = indptr.len().saturating_sub(1);
+        Self::try_from_csr_with_ncols(indptr, indices, ncols)
+    }
+
+    /// Builds a GraphMatrix with `ncols` columns from existing CSR arrays, checking that they
+    /// are well-formed.
+    pub fn try_from_csr_with_ncols(
+        indptr: Vec<usize>,
+        indices: Vec<T>,
+        ncols: usize,
+    ) -> Result<Self, GraphMatrixError> {
+        validate_csr(&indptr, &indices, ncols)?;
+        Ok(GraphMatrix {indptr, indices, ncols})
+    }
+
+    /// Builds a GraphMatrix with `ncols` columns from existing CSR arrays without checking them.
+    ///
+    /// # Safety
+    ///
+    /// The arrays must satisfy the invariants checked by `try_from_csr_with_ncols`: `indptr`
+    /// is non-empty, starts at zero, never decreases and ends at `indices.len()`, each row's
+    /// indices are strictly increasing and less than `ncols`, and every row and column
+    /// number below `nrows` and `ncols` is representable in `T`.
+    pub unsafe fn from_csr_unchecked(indptr: Vec<usize>, indices: Vec<T>, ncols: usize) -> Self {
+        GraphMatrix {indptr, indices, ncols}
+    }
+
+    /// Splits the matrix into `(indptr, indices, ncols)` without copying.
+    pub fn into_raw_parts(self) -> (Vec<usize>, Vec<T>, usize) {
+        (self.indptr, self.indices, self.ncols)
+    }
+
+    fn sorted_edges(edgelist: Vec<(T, T)>) -> (Vec<T>, Vec<T>) {
         let mut sorted_edgelist = edgelist;
         sorted_edgelist.sort_unstable();
         sorted_edgelist.dedup();
-        let (ss, ds): (Vec<_>, Vec<_>) = sorted_edgelist.into_iter().unzip();
+        sorted_edgelist.into_iter().unzip()
+    }
 
-        let m1 = ss.last().ok_or(GraphMatrixError::InvalidIndex)?;
-        let m2 = ds.iter().max().ok_or(GraphMatrixError::InvalidIndex)?;
-        let m = m1
-            .max(m2)
-            .to_usize()
-            .ok_or(GraphMatrixError::InvalidIndex)? + 1;
-        let (indptr, indices) = compress(ss, ds, m)?;
-        Ok(GraphMatrix {indptr, indices})
+    fn from_sorted_edges(
+        ss: Vec<T>,
+        ds: Vec<T>,
+        nrows: usize,
+        ncols: usize,
+    ) -> Result<Self, GraphMatrixError> {
+        let vals = vec![(); ss.len()];
+        let (indptr, indices, _) = compress(ss, ds, vals, nrows)?;
+        Ok(GraphMatrix {indptr, indices, ncols})
     }
 }
 
-#[derive(Debug)]
+/// Iterates over every `(row, col)` element of a GraphMatrix or GraphMatrixView in row-major
+/// order.
+#[derive(Debug, Clone)]
 pub struct GraphMatrixIterator<'a, T: 'a> {
-    gm: &'a GraphMatrix<T>,
-    curr_rownum: T,
-    curr_colptr: usize,
+    gm: GraphMatrixView<'a, T>,
+    // row holding the element at `front`
+    front_row: usize,
+    front: usize,
+    // row holding the element at `back - 1`
+    back_row: usize,
+    back: usize,
 }
 
 impl<'a, T: num::PrimInt> GraphMatrixIterator<'a, T> {
     pub fn new(g: &'a GraphMatrix<T>) -> Self {
-        GraphMatrixIterator{gm: g, curr_rownum: T::zero(), curr_colptr: 0}
+        Self::from_view(g.view())
+    }
+
+    pub(crate) fn from_view(g: GraphMatrixView<'a, T>) -> Self {
+        let back_row = g.indptr.len().saturating_sub(2);
+        GraphMatrixIterator{gm: g, front_row: 0, front: 0, back_row, back: g.ne()}
+    }
+
+    fn element(&self, row: usize, pos: usize) -> (T, T) {
+        // every row that holds an element was given to us as a T, so this can't fail.
+        let r = T::from(row).expect("row index fits in T");
+        (r, self.gm.indices[pos])
     }
 }
 
-impl<'a, T:num::PrimInt + std::fmt::Display> Iterator for GraphMatrixIterator<'a, T> where T: PrimInt {
+impl<'a, T> Iterator for GraphMatrixIterator<'a, T> where T: PrimInt {
     type Item = (T, T);
     fn next(&mut self) -> Option<(T, T)> {
-        let row_data = self.gm.row(self.curr_rownum).ok()?;
-        let v = (self.curr_rownum, row_data[self.curr_colptr]);
-        self.curr_colptr += 1;
-        if self.curr_colptr >= row_data.len() {
-            self.curr_rownum = self.curr_rownum + T::one();
-            self.curr_colptr = 0;
+        if self.front >= self.back {
+            return None;
+        }
+        while self.gm.indptr[self.front_row + 1] <= self.front {
+            self.front_row += 1;
         }
+        let v = self.element(self.front_row, self.front);
+        self.front += 1;
         Some(v)
     }
+
+    fn size_hint(&self) -> (usize, Option<usize>) {
+        let n = self.back - self.front;
+        (n, Some(n))
+    }
+}
+
+impl<'a, T> DoubleEndedIterator for GraphMatrixIterator<'a, T> where T: PrimInt {
+    fn next_back(&mut self) -> Option<(T, T)> {
+        if self.front >= self.back {
+            return None;
+        }
+        self.back -= 1;
+        while self.gm.indptr[self.back_row] > self.back {
+            self.back_row -= 1;
+        }
+        Some(self.element(self.back_row, self.back))
+    }
+}
+
+impl<'a, T> ExactSizeIterator for GraphMatrixIterator<'a, T> where T: PrimInt {}
+
+impl<'a, T> std::iter::FusedIterator for GraphMatrixIterator<'a, T> where T: PrimInt {}
+
+impl<'a, T> IntoIterator for &'a GraphMatrix<T> where T: PrimInt {
+    type Item = (T, T);
+    type IntoIter = GraphMatrixIterator<'a, T>;
+    fn into_iter(self) -> Self::IntoIter {
+        GraphMatrixIterator::new(self)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// Rows 0, 1 and 3 are empty, as is column 4.
+    fn gappy() -> GraphMatrix<u32> {
+        let edges = vec![(2, 0), (2, 3), (4, 1), (5, 0), (5, 2), (5, 5)];
+        GraphMatrix::from_edgelist_with_dims(edges, 7, 6).unwrap()
+    }
+
+    #[test]
+    fn from_edgelist_with_dims_rejects_out_of_range_endpoints() {
+        let err = GraphMatrix::<u32>::from_edgelist_with_dims(vec![(0, 1), (3, 0)], 3, 3);
+        let expected = GraphMatrixError::BoundsError {index: 3, bound: 3, edge: Some(1)};
+        assert_eq!(err.unwrap_err(), expected);
+        let err = GraphMatrix::<u32>::from_edgelist_with_dims(vec![(1, 4), (0, 1)], 4, 4);
+        let expected = GraphMatrixError::BoundsError {index: 4, bound: 4, edge: Some(0)};
+        assert_eq!(err.unwrap_err(), expected);
+    }
+
+    #[test]
+    fn from_edgelist_with_dims_keeps_isolated_vertices() {
+        let g = GraphMatrix::<u32>::from_edgelist_with_dims(vec![(0, 1), (1, 0)], 5, 5).unwrap();
+        assert_eq!(g.dims(), (5, 5));
+        assert_eq!(g.ne(), 2);
+        assert_eq!(g.row_len(4).unwrap(), 0);
+        let g = GraphMatrix::<u32>::from_edgelist_with_dims(vec![(0, 6), (2, 1)], 3, 7).unwrap();
+        assert_eq!(g.dims(), (3, 7));
+        assert!(g.has_index(0, 6).unwrap());
+        assert!(g.row(1).unwrap().is_empty());
+        assert_eq!(g.row(2).unwrap(), &[1]);
+    }
+
+    #[test]
+    fn iter_skips_empty_rows() {
+        let expected = vec![(2, 0), (2, 3), (4, 1), (5, 0), (5, 2), (5, 5)];
+        assert_eq!(gappy().iter().collect::<Vec<_>>(), expected);
+        let g = GraphMatrix::<u32>::from_edgelist_with_dims(vec![], 3, 3).unwrap();
+        assert_eq!(g.iter().next(), None);
+    }
+
+    #[test]
+    fn iter_runs_backwards() {
+        let g = gappy();
+        let mut expected: Vec<(u32, u32)> = g.iter().collect();
+        expected.reverse();
+        assert_eq!(g.iter().rev().collect::<Vec<_>>(), expected);
+    }
+
+    #[test]
+    fn iter_len_counts_from_both_ends() {
+        let g = gappy();
+        let mut it = g.iter();
+        assert_eq!(it.len(), 6);
+        assert_eq!(it.next(), Some((2, 0)));
+        assert_eq!(it.next_back(), Some((5, 5)));
+        assert_eq!(it.next_back(), Some((5, 2)));
+        assert_eq!(it.len(), 3);
+        assert_eq!(it.next(), Some((2, 3)));
+        assert_eq!(it.next_back(), Some((5, 0)));
+        assert_eq!(it.len(), 1);
+        assert_eq!(it.next_back(), Some((4, 1)));
+        assert_eq!(it.len(), 0);
+        assert_eq!(it.next(), None);
+        assert_eq!(it.next_back(), None);
+        assert_eq!(it.next(), None);
+    }
+
+    #[test]
+    fn raw_parts_round_trip() {
+        let g = gappy();
+        let (indptr, indices, ncols) = g.into_raw_parts();
+        assert_eq!(indptr, vec![0, 0, 0, 2, 2, 3, 6, 6]);
+        assert_eq!(indices, vec![0, 3, 1, 0, 2, 5]);
+        let g = GraphMatrix::try_from_csr_with_ncols(indptr.clone(), indices.clone(), ncols);
+        let (indptr2, indices2, ncols2) = g.unwrap().into_raw_parts();
+        assert_eq!((indptr2, indices2, ncols2), (indptr, indices, 6));
+    }
+
+    fn corrupt(row: Option<usize>, reason: &'static str) -> GraphMatrixError {
+        GraphMatrixError::CorruptStructure {row, reason: reason.into()}
+    }
+
+    #[test]
+    fn try_from_csr_accepts_valid_input() {
+        let g = GraphMatrix::<u32>::try_from_csr(vec![0, 2, 2, 3], vec![0, 2, 1]).unwrap();
+        assert_eq!(g.dims(), (3, 3));
+        assert_eq!(g.iter().collect::<Vec<_>>(), vec![(0, 0), (0, 2), (2, 1)]);
+    }
+
+    #[test]
+    fn transpose_reorders_rows() {
+        let g = GraphMatrix::<u32>::from_edgelist_with_dims(vec![(0, 2), (1, 0), (1, 2)], 2, 3)
+            .unwrap();
+        let t = g.transpose().unwrap();
+        assert_eq!(t.dims(), (3, 2));
+        assert_eq!(t.iter().collect::<Vec<_>>(), vec![(0, 1), (2, 0), (2, 1)]);
+    }
+
+    #[test]
+    fn try_from_csr_rejects_non_monotone_indptr() {
+        let err = GraphMatrix::<u32>::try_from_csr(vec![0, 2, 1, 2], vec![0, 1]).unwrap_err();
+        assert_eq!(err, corrupt(Some(1), "indptr is not monotone"));
+    }
+
+    #[test]
+    fn try_from_csr_rejects_overshooting_indptr() {
+        let err = GraphMatrix::<u32>::try_from_csr(vec![0, 5, 2], vec![0, 1]).unwrap_err();
+        assert_eq!(err, corrupt(Some(0), "indptr points past the end of indices"));
+    }
+
+    #[test]
+    fn try_from_csr_rejects_bad_endpoints() {
+        let err = GraphMatrix::<u32>::try_from_csr(vec![], vec![]).unwrap_err();
+        assert_eq!(err, corrupt(None, "indptr is empty"));
+        let err = GraphMatrix::<u32>::try_from_csr(vec![1, 2], vec![0, 1]).unwrap_err();
+        assert_eq!(err, corrupt(None, "indptr does not start at zero"));
+        let err = GraphMatrix::<u32>::try_from_csr(vec![0, 1], vec![0, 1]).unwrap_err();
+        assert_eq!(err, corrupt(None, "last indptr entry does not match the number of indices"));
+    }
+
+    #[test]
+    fn try_from_csr_rejects_unsorted_rows() {
+        let err = GraphMatrix::<u32>::try_from_csr(vec![0, 2, 2], vec![1, 0]).unwrap_err();
+        assert_eq!(err, corrupt(Some(0), "row indices are not sorted and unique"));
+        let err = GraphMatrix::<u32>::try_from_csr(vec![0, 2, 2], vec![1, 1]).unwrap_err();
+        assert_eq!(err, corrupt(Some(0), "row indices are not sorted and unique"));
+    }
+
+    #[test]
+    fn try_from_csr_rejects_out_of_bounds_indices() {
+        let err = GraphMatrix::<u32>::try_from_csr(vec![0, 1, 2], vec![0, 2]).unwrap_err();
+        assert_eq!(err, GraphMatrixError::BoundsError {index: 2, bound: 2, edge: Some(1)});
+        let err = GraphMatrix::<i32>::try_from_csr(vec![0, 1], vec![-1]).unwrap_err();
+        assert_eq!(err, GraphMatrixError::invalid_index(-1, Some(0)));
+    }
+
+    #[test]
+    fn view_try_from_csr_rejects_overshooting_indptr() {
+        let err = GraphMatrixView::<u32>::try_from_csr(&[0, 5, 2], &[0, 1], 2).unwrap_err();
+        assert_eq!(err, corrupt(Some(0), "indptr points past the end of indices"));
+    }
 }
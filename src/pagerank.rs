@@ -0,0 +1,278 @@
+//! PageRank by power iteration over a square GraphMatrix.
+//!
+//! The random surfer follows an out-edge with probability `damping` and otherwise teleports
+//! according to the personalization vector (uniform if none is given). Surfers on dangling
+//! vertices (empty rows) always teleport, so scores keep summing to one.
+
+use num::PrimInt;
+#[cfg(feature = "rayon")]
+use rayon::prelude::*;
+
+use crate::{index, BiGraphMatrix, GraphMatrix, GraphMatrixError};
+
+/// Parameters for the power iteration.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct PageRankConfig {
+    /// The probability of following an out-edge rather than teleporting.
+    pub damping: f64,
+    /// Iteration stops once the L1 change in scores falls below this.
+    pub tolerance: f64,
+    pub max_iterations: usize,
+}
+
+impl Default for PageRankConfig {
+    fn default() -> Self {
+        PageRankConfig {damping: 0.85, tolerance: 1e-6, max_iterations: 100}
+    }
+}
+
+/// The result of a PageRank computation.
+#[derive(Debug, Clone, PartialEq)]
+pub struct PageRank {
+    /// One score per vertex, summing to one.
+    pub scores: Vec<f64>,
+    /// The L1 change in scores made by each iteration.
+    pub history: Vec<f64>,
+    /// Whether the last change was below the tolerance.
+    pub converged: bool,
+}
+
+/// Checks the configuration and returns the normalized teleport distribution.
+fn teleport(
+    n: usize,
+    config: &PageRankConfig,
+    personalization: Option<&[f64]>,
+) -> Result<Vec<f64>, GraphMatrixError> {
+    if !(0.0..=1.0).contains(&config.damping) {
+        return Err(GraphMatrixError::invalid_parameter("damping must be in [0, 1]"))
+    }
+    if !config.tolerance.is_finite() || config.tolerance < 0.0 {
+        return Err(GraphMatrixError::invalid_parameter("tolerance must be finite and non-negative"))
+    }
+    let Some(p) = personalization else {
+        return Ok(vec![1.0 / n as f64; n]);
+    };
+    if p.len() != n {
+        return Err(GraphMatrixError::DimensionMismatch {expected: n, found: p.len()})
+    }
+    if p.iter().any(|&x| !x.is_finite() || x < 0.0) {
+        let reason = "personalization entries must be finite and non-negative";
+        return Err(GraphMatrixError::invalid_parameter(reason))
+    }
+    // finite entries can still overflow when summed.
+    let total: f64 = p.iter().sum();
+    if !total.is_finite() || total <= 0.0 {
+        let reason = "personalization must have a finite, positive sum";
+        return Err(GraphMatrixError::invalid_parameter(reason))
+    }
+    Ok(p.iter().map(|&x| x / total).collect())
+}
+
+/// Runs the power iteration. `spread` adds the link contributions to `next`, given each
+/// vertex's damped score divided by its out-degree.
+fn iterate<T, F>(
+    out: &GraphMatrix<T>,
+    config: &PageRankConfig,
+    personalization: Option<&[f64]>,
+    mut spread: F,
+) -> Result<PageRank, GraphMatrixError>
+where
+    T: PrimInt,
+    F: FnMut(&[f64], &mut [f64]),
+{
+    let n = out.nv()?;
+    let p = teleport(n, config, personalization)?;
+    let degree: Vec<usize> = out.indptr.windows(2).map(|w| w[1] - w[0]).collect();
+    let mut scores = p.clone();
+    let mut next = vec![0.0; n];
+    let mut contrib = vec![0.0; n];
+    let mut history = Vec::new();
+    let mut converged = n == 0;
+
+    for _ in 0..config.max_iterations {
+        if converged {
+            break;
+        }
+        let mut dangling = 0.0;
+        for u in 0..n {
+            if degree[u] == 0 {
+                dangling += scores[u];
+                contrib[u] = 0.0;
+            } else {
+                contrib[u] = config.damping * scores[u] / degree[u] as f64;
+            }
+        }
+        // scores sum to one, so this is the mass that teleports.
+        let teleported = 1.0 - config.damping + config.damping * dangling;
+        for (x, &pv) in next.iter_mut().zip(&p) {
+            *x = teleported * pv;
+        }
+        spread(&contrib, &mut next);
+        let change: f64 = scores.iter().zip(&next).map(|(a, b)| (a - b).abs()).sum();
+        history.push(change);
+        std::mem::swap(&mut scores, &mut next);
+        converged = change < config.tolerance;
+    }
+    Ok(PageRank {scores, history, converged})
+}
+
+/// Push-based PageRank: each vertex adds its share to every out-neighbor.
+pub fn pagerank<T: PrimInt>(
+    g: &GraphMatrix<T>,
+    config: &PageRankConfig,
+    personalization: Option<&[f64]>,
+) -> Result<PageRank, GraphMatrixError> {
+    iterate(g, config, personalization, |contrib, next| {
+        for (u, w) in g.indptr.windows(2).enumerate() {
+            for &v in &g.indices[w[0]..w[1]] {
+                next[index(v)] += contrib[u];
+            }
+        }
+    })
+}
+
+/// Pull-based PageRank: each vertex sums the shares of its in-neighbors, read from the
+/// transpose. Every score is written by exactly one vertex, with no scattered updates.
+pub fn pagerank_pull<T: PrimInt>(
+    g: &BiGraphMatrix<T>,
+    config: &PageRankConfig,
+    personalization: Option<&[f64]>,
+) -> Result<PageRank, GraphMatrixError> {
+    let bwd = g.backward();
+    iterate(g.forward(), config, personalization, |contrib, next| {
+        for (v, x) in next.iter_mut().enumerate() {
+            *x += bwd.row_slice(v).iter().map(|&u| contrib[index(u)]).sum::<f64>();
+        }
+    })
+}
+
+/// Pull-based PageRank with the per-vertex sums spread across the rayon thread pool.
+#[cfg(feature = "rayon")]
+pub fn par_pagerank<T: PrimInt + Sync>(
+    g: &BiGraphMatrix<T>,
+    config: &PageRankConfig,
+    personalization: Option<&[f64]>,
+) -> Result<PageRank, GraphMatrixError> {
+    let bwd = g.backward();
+    iterate(g.forward(), config, personalization, |contrib, next| {
+        next.par_iter_mut().enumerate().for_each(|(v, x)| {
+            *x += bwd.row_slice(v).iter().map(|&u| contrib[index(u)]).sum::<f64>();
+        });
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use rand::rngs::StdRng;
+    use rand::{Rng, SeedableRng};
+
+    use super::*;
+
+    /// A random digraph, with about a fifth of its vertices left dangling.
+    fn random_graph(n: u32, m: usize, seed: u64) -> GraphMatrix<u32> {
+        let mut rng = StdRng::seed_from_u64(seed);
+        let edges = (0..m)
+            .map(|_| (rng.gen_range(0..n * 4 / 5), rng.gen_range(0..n)))
+            .collect();
+        GraphMatrix::from_edgelist_with_dims(edges, n as usize, n as usize).unwrap()
+    }
+
+    fn assert_close(a: &[f64], b: &[f64]) {
+        assert_eq!(a.len(), b.len());
+        for (x, y) in a.iter().zip(b) {
+            assert!((x - y).abs() < 1e-9, "{} != {}", x, y);
+        }
+    }
+
+    #[test]
+    fn scores_sum_to_one() {
+        let config = PageRankConfig {tolerance: 1e-10, ..Default::default()};
+        for seed in 0..4 {
+            let g = random_graph(100, 300, seed);
+            let res = pagerank(&g, &config, None).unwrap();
+            assert!(res.converged);
+            assert!((res.scores.iter().sum::<f64>() - 1.0).abs() < 1e-9);
+            assert!(res.history.windows(2).all(|w| w[1] <= w[0] + 1e-12));
+        }
+    }
+
+    #[test]
+    fn push_and_pull_agree() {
+        let config = PageRankConfig::default();
+        let p: Vec<f64> = (0..100).map(|v| (v % 7) as f64).collect();
+        for seed in 0..4 {
+            let g = random_graph(100, 300, seed);
+            let push = pagerank(&g, &config, Some(&p)).unwrap();
+            let bi = BiGraphMatrix::new(random_graph(100, 300, seed)).unwrap();
+            let pull = pagerank_pull(&bi, &config, Some(&p)).unwrap();
+            assert_close(&push.scores, &pull.scores);
+            assert_eq!(push.history.len(), pull.history.len());
+        }
+    }
+
+    #[cfg(feature = "rayon")]
+    #[test]
+    fn par_pagerank_agrees() {
+        let config = PageRankConfig::default();
+        let g = random_graph(500, 2000, 5);
+        let push = pagerank(&g, &config, None).unwrap();
+        let bi = BiGraphMatrix::new(random_graph(500, 2000, 5)).unwrap();
+        assert_close(&push.scores, &par_pagerank(&bi, &config, None).unwrap().scores);
+    }
+
+    #[test]
+    fn dangling_mass_teleports() {
+        // 1 is dangling, so s0 = (1 - d) / 2 + d * s1 / 2, which solves to s0 = 1 / (2 + d).
+        let g = GraphMatrix::<u32>::from_edgelist(vec![(0, 1)]).unwrap();
+        let config = PageRankConfig {tolerance: 1e-12, ..Default::default()};
+        let res = pagerank(&g, &config, None).unwrap();
+        let s0 = 1.0 / 2.85;
+        assert_close(&res.scores, &[s0, 1.0 - s0]);
+        let g = GraphMatrix::<u32>::from_edgelist_with_dims(vec![], 4, 4).unwrap();
+        assert_eq!(pagerank(&g, &config, None).unwrap().scores, vec![0.25; 4]);
+    }
+
+    #[test]
+    fn personalization_biases_the_scores() {
+        let g = GraphMatrix::<u32>::from_edgelist(vec![(0, 1), (1, 2), (2, 0), (2, 3), (3, 0)])
+            .unwrap();
+        let config = PageRankConfig::default();
+        let uniform = pagerank(&g, &config, None).unwrap().scores;
+        let p = [0.0, 0.0, 0.0, 2.0];
+        let biased = pagerank(&g, &config, Some(&p)).unwrap().scores;
+        assert!(biased[3] > uniform[3]);
+        assert!((biased.iter().sum::<f64>() - 1.0).abs() < 1e-9);
+        // without links, everyone teleports
+        let config = PageRankConfig {damping: 0.0, ..config};
+        assert_close(&pagerank(&g, &config, Some(&p)).unwrap().scores, &[0.0, 0.0, 0.0, 1.0]);
+    }
+
+    #[test]
+    fn rejects_invalid_parameters() {
+        let g = GraphMatrix::<u32>::from_edgelist(vec![(0, 1), (1, 2), (2, 0)]).unwrap();
+        let config = PageRankConfig {damping: 1.5, ..Default::default()};
+        assert!(is_invalid(pagerank(&g, &config, None)));
+        let config = PageRankConfig::default();
+        let err = pagerank(&g, &config, Some(&[1.0, 1.0])).unwrap_err();
+        assert_eq!(err, GraphMatrixError::DimensionMismatch {expected: 3, found: 2});
+        assert!(is_invalid(pagerank(&g, &config, Some(&[1.0, -1.0, 1.0]))));
+        assert!(is_invalid(pagerank(&g, &config, Some(&[0.0, 0.0, 0.0]))));
+    }
+
+    fn is_invalid(r: Result<PageRank, GraphMatrixError>) -> bool {
+        matches!(r, Err(GraphMatrixError::InvalidParameter { .. }))
+    }
+
+    #[test]
+    fn rejects_non_finite_parameters() {
+        let g = GraphMatrix::<u32>::from_edgelist(vec![(0, 1), (1, 2), (2, 0)]).unwrap();
+        let config = PageRankConfig::default();
+        for p in [[1.0, f64::INFINITY, 0.0], [1.0, f64::NAN, 0.0], [f64::MAX, f64::MAX, 0.0]] {
+            assert!(is_invalid(pagerank(&g, &config, Some(&p))));
+        }
+        for tolerance in [f64::NAN, f64::INFINITY, -1.0] {
+            let config = PageRankConfig {tolerance, ..config};
+            assert!(is_invalid(pagerank(&g, &config, None)));
+        }
+    }
+}
@@ -0,0 +1,347 @@
+//! petgraph's graph traits for GraphMatrix, so petgraph's algorithms and visitors run on it
+//! directly. A matrix is seen as a directed graph on `max(nrows, ncols)` vertices, where
+//! vertices past the last row have no out-edges, and edges are identified by their
+//! `(source, target)` pair.
+
+use std::iter::{Copied, Map, Repeat, Zip};
+use std::ops::Range;
+use std::slice::Iter;
+
+use num::PrimInt;
+use petgraph::csr::Csr;
+use petgraph::graph::IndexType;
+use petgraph::visit::{
+    Data, EdgeCount, GraphBase, GraphProp, IntoEdgeReferences, IntoEdges, IntoNeighbors,
+    IntoNodeIdentifiers, NodeCompactIndexable, NodeCount, NodeIndexable, VisitMap, Visitable,
+};
+use petgraph::{Directed, EdgeType, Graph};
+
+use crate::{from_usize, Bitset, GraphMatrix, GraphMatrixError, GraphMatrixIterator};
+
+impl<T> GraphMatrix<T> where T: PrimInt {
+
+    /// The number of vertices petgraph sees.
+    fn node_bound(&self) -> usize {
+        let (nrows, ncols) = self.dims();
+        nrows.max(ncols)
+    }
+}
+
+/// Converts a vertex position to `T`. Every position below the node bound fits, since the
+/// dimensions of a GraphMatrix do.
+fn node<T: PrimInt>(i: usize) -> T {
+    from_usize(i).expect("vertex fits in the index type")
+}
+
+fn edge_ref<'a, T>((s, d): (T, T)) -> (T, T, &'a ()) {
+    (s, d, &())
+}
+
+/// Checks that `n` vertices can be numbered by `Ix`, whose maximum value petgraph reserves.
+fn check_ix<Ix: IndexType>(n: usize) -> Result<(), GraphMatrixError> {
+    if n > <Ix as IndexType>::max().index() {
+        return Err(GraphMatrixError::Overflow {value: n - 1})
+    }
+    Ok(())
+}
+
+impl<T> GraphBase for GraphMatrix<T> where T: PrimInt {
+    type NodeId = T;
+    type EdgeId = (T, T);
+}
+
+impl<T> Data for GraphMatrix<T> where T: PrimInt {
+    type NodeWeight = ();
+    type EdgeWeight = ();
+}
+
+impl<T> GraphProp for GraphMatrix<T> where T: PrimInt {
+    type EdgeType = Directed;
+}
+
+impl<T> NodeCount for GraphMatrix<T> where T: PrimInt {
+    fn node_count(&self) -> usize {
+        self.node_bound()
+    }
+}
+
+impl<T> EdgeCount for GraphMatrix<T> where T: PrimInt {
+    fn edge_count(&self) -> usize {
+        self.ne()
+    }
+}
+
+impl<T> NodeIndexable for GraphMatrix<T> where T: PrimInt {
+    fn node_bound(&self) -> usize {
+        GraphMatrix::node_bound(self)
+    }
+
+    fn to_index(&self, a: T) -> usize {
+        a.to_usize().expect("vertex is non-negative")
+    }
+
+    fn from_index(&self, i: usize) -> T {
+        node(i)
+    }
+}
+
+impl<T> NodeCompactIndexable for GraphMatrix<T> where T: PrimInt {}
+
+impl<T> Visitable for GraphMatrix<T> where T: PrimInt {
+    type Map = Bitset;
+
+    fn visit_map(&self) -> Bitset {
+        Bitset::new(GraphMatrix::node_bound(self))
+    }
+
+    fn reset_map(&self, map: &mut Bitset) {
+        map.clear();
+    }
+}
+
+/// Vertices are visited by position. Like the rest of Bitset, panics on a vertex past its
+/// length.
+impl<T> VisitMap<T> for Bitset where T: PrimInt {
+    fn visit(&mut self, a: T) -> bool {
+        self.insert(a.to_usize().expect("vertex is non-negative"))
+    }
+
+    fn is_visited(&self, a: &T) -> bool {
+        a.to_usize().is_some_and(|i| self.contains(i))
+    }
+
+    fn unvisit(&mut self, a: T) -> bool {
+        a.to_usize().is_some_and(|i| self.remove(i))
+    }
+}
+
+impl<'a, T> IntoNeighbors for &'a GraphMatrix<T> where T: PrimInt {
+    type Neighbors = Copied<Iter<'a, T>>;
+
+    /// Panics if `a` is not a vertex.
+    fn neighbors(self, a: T) -> Self::Neighbors {
+        let v = self.to_index(a);
+        assert!(v < GraphMatrix::node_bound(self), "vertex {} out of bounds", v);
+        let row = if v < self.dims().0 { self.row_slice(v) } else { &[] };
+        row.iter().copied()
+    }
+}
+
+impl<T> IntoNodeIdentifiers for &GraphMatrix<T> where T: PrimInt {
+    type NodeIdentifiers = Map<Range<usize>, fn(usize) -> T>;
+
+    fn node_identifiers(self) -> Self::NodeIdentifiers {
+        (0..GraphMatrix::node_bound(self)).map(node as fn(usize) -> T)
+    }
+}
+
+impl<'a, T> IntoEdgeReferences for &'a GraphMatrix<T> where T: PrimInt {
+    type EdgeRef = (T, T, &'a ());
+    type EdgeReferences = Map<GraphMatrixIterator<'a, T>, fn((T, T)) -> (T, T, &'a ())>;
+
+    fn edge_references(self) -> Self::EdgeReferences {
+        self.iter().map(edge_ref as fn((T, T)) -> (T, T, &'a ()))
+    }
+}
+
+impl<'a, T> IntoEdges for &'a GraphMatrix<T> where T: PrimInt {
+    type Edges = Map<Zip<Repeat<T>, Copied<Iter<'a, T>>>, fn((T, T)) -> (T, T, &'a ())>;
+
+    /// Panics if `a` is not a vertex.
+    fn edges(self, a: T) -> Self::Edges {
+        std::iter::repeat(a)
+            .zip(self.neighbors(a))
+            .map(edge_ref as fn((T, T)) -> (T, T, &'a ()))
+    }
+}
+
+/// Copies the matrix into a petgraph Csr on `max(nrows, ncols)` vertices. Returns
+/// `GraphMatrixError::Overflow` if the vertices do not fit in `Ix`.
+impl<T, Ix> TryFrom<&GraphMatrix<T>> for Csr<(), (), Directed, Ix>
+where
+    T: PrimInt,
+    Ix: IndexType,
+{
+    type Error = GraphMatrixError;
+
+    fn try_from(g: &GraphMatrix<T>) -> Result<Self, GraphMatrixError> {
+        let n = GraphMatrix::node_bound(g);
+        check_ix::<Ix>(n)?;
+        let ix = |v: T| Ix::new(g.to_index(v));
+        let edges: Vec<(Ix, Ix)> = g.iter().map(|(s, d)| (ix(s), ix(d))).collect();
+        let mut csr = Csr::from_sorted_edges(&edges).expect("matrix elements are sorted");
+        while csr.node_count() < n {
+            csr.add_node(());
+        }
+        Ok(csr)
+    }
+}
+
+/// Copies the matrix into a petgraph Graph on `max(nrows, ncols)` vertices. Returns
+/// `GraphMatrixError::Overflow` if the vertices do not fit in `Ix`.
+impl<T, Ix> TryFrom<&GraphMatrix<T>> for Graph<(), (), Directed, Ix>
+where
+    T: PrimInt,
+    Ix: IndexType,
+{
+    type Error = GraphMatrixError;
+
+    fn try_from(g: &GraphMatrix<T>) -> Result<Self, GraphMatrixError> {
+        let n = GraphMatrix::node_bound(g);
+        check_ix::<Ix>(n)?;
+        let mut graph = Graph::with_capacity(n, g.ne());
+        for _ in 0..n {
+            graph.add_node(());
+        }
+        let ix = |v: T| Ix::new(g.to_index(v));
+        graph.extend_with_edges(g.iter().map(|(s, d)| (ix(s), ix(d))));
+        Ok(graph)
+    }
+}
+
+/// Builds a square GraphMatrix with the structure of a petgraph Csr, dropping its weights.
+/// Returns `GraphMatrixError::Overflow` if a vertex does not fit in `T`.
+impl<N, E, Ty, Ix, T> TryFrom<&Csr<N, E, Ty, Ix>> for GraphMatrix<T>
+where
+    Ty: EdgeType,
+    Ix: IndexType,
+    T: PrimInt,
+{
+    type Error = GraphMatrixError;
+
+    fn try_from(csr: &Csr<N, E, Ty, Ix>) -> Result<Self, GraphMatrixError> {
+        let n = csr.node_count();
+        let mut indptr = Vec::with_capacity(n + 1);
+        let mut indices = Vec::new();
+        indptr.push(0);
+        for v in 0..n {
+            for d in csr.neighbors_slice(Ix::new(v)) {
+                indices.push(from_usize(d.index())?);
+            }
+            indptr.push(indices.len());
+        }
+        GraphMatrix::try_from_csr(indptr, indices)
+    }
+}
+
+/// Builds a square GraphMatrix with the structure of a petgraph Graph, dropping its weights.
+/// An undirected edge becomes a pair of opposite elements, and parallel edges become one.
+/// Returns `GraphMatrixError::Overflow` if a vertex does not fit in `T`.
+impl<N, E, Ty, Ix, T> TryFrom<&Graph<N, E, Ty, Ix>> for GraphMatrix<T>
+where
+    Ty: EdgeType,
+    Ix: IndexType,
+    T: PrimInt,
+{
+    type Error = GraphMatrixError;
+
+    fn try_from(graph: &Graph<N, E, Ty, Ix>) -> Result<Self, GraphMatrixError> {
+        let n = graph.node_count();
+        let mut edges = Vec::with_capacity(graph.edge_count());
+        for e in graph.raw_edges() {
+            let (s, d) = (from_usize(e.source().index())?, from_usize(e.target().index())?);
+            edges.push((s, d));
+            if !graph.is_directed() {
+                edges.push((d, s));
+            }
+        }
+        GraphMatrix::from_edgelist_with_dims(edges, n, n)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use petgraph::algo::{connected_components, dijkstra};
+    use petgraph::visit::{Bfs, Dfs};
+    use petgraph::Undirected;
+    use rand::rngs::StdRng;
+    use rand::{Rng, SeedableRng};
+
+    use super::*;
+    use crate::components::weakly_connected;
+    use crate::shortest_paths::unweighted;
+
+    fn random_graph(n: u32, m: usize, seed: u64) -> GraphMatrix<u32> {
+        let mut rng = StdRng::seed_from_u64(seed);
+        let edges = (0..m).map(|_| (rng.gen_range(0..n), rng.gen_range(0..n))).collect();
+        GraphMatrix::from_edgelist_with_dims(edges, n as usize, n as usize).unwrap()
+    }
+
+    fn edges(g: &GraphMatrix<u32>) -> Vec<(u32, u32)> {
+        g.iter().collect()
+    }
+
+    #[test]
+    fn searches_reach_what_bfs_reaches() {
+        for seed in 0..4 {
+            let g = random_graph(200, 300, seed);
+            let reached: Vec<bool> =
+                unweighted(&g, 0).unwrap().distances.iter().map(Option::is_some).collect();
+            let mut dfs_seen = vec![false; 200];
+            let mut dfs = Dfs::new(&g, 0);
+            while let Some(v) = dfs.next(&g) {
+                assert!(!dfs_seen[v as usize]);
+                dfs_seen[v as usize] = true;
+            }
+            assert_eq!(dfs_seen, reached);
+            let mut bfs = Bfs::new(&g, 0);
+            let mut count = 0;
+            while let Some(v) = bfs.next(&g) {
+                assert!(reached[v as usize]);
+                count += 1;
+            }
+            assert_eq!(count, reached.iter().filter(|&&r| r).count());
+        }
+    }
+
+    #[test]
+    fn algorithms_agree_with_the_crate() {
+        for seed in 0..4 {
+            let g = random_graph(200, 300, seed);
+            let costs = dijkstra(&g, 0, None, |_| 1usize);
+            for (v, d) in unweighted(&g, 0).unwrap().distances.into_iter().enumerate() {
+                assert_eq!(costs.get(&(v as u32)).copied(), d);
+            }
+            let mut labels = weakly_connected(&g).unwrap();
+            labels.sort_unstable();
+            labels.dedup();
+            assert_eq!(connected_components(&g), labels.len());
+        }
+    }
+
+    #[test]
+    fn conversions_round_trip() {
+        let g = random_graph(100, 400, 25);
+        let csr = Csr::<(), (), Directed, u32>::try_from(&g).unwrap();
+        assert_eq!(edges(&GraphMatrix::try_from(&csr).unwrap()), edges(&g));
+        let graph = Graph::<(), (), Directed, u32>::try_from(&g).unwrap();
+        assert_eq!(graph.edge_count(), g.ne());
+        assert_eq!(edges(&GraphMatrix::try_from(&graph).unwrap()), edges(&g));
+        // a rectangular matrix becomes a square graph.
+        let g = GraphMatrix::<u32>::from_edgelist_with_dims(vec![(0, 3), (1, 0)], 2, 4).unwrap();
+        let graph = Graph::<(), (), Directed, u32>::try_from(&g).unwrap();
+        let h = GraphMatrix::<u32>::try_from(&graph).unwrap();
+        assert_eq!(h.dims(), (4, 4));
+        assert_eq!(edges(&h), vec![(0, 3), (1, 0)]);
+    }
+
+    #[test]
+    fn undirected_graphs_become_symmetric() {
+        let mut graph = Graph::<&str, f64, Undirected>::new_undirected();
+        let (a, b, c) = (graph.add_node("a"), graph.add_node("b"), graph.add_node("c"));
+        graph.extend_with_edges([(a, b, 1.0), (b, c, 2.0), (a, b, 3.0)]);
+        let g = GraphMatrix::<u8>::try_from(&graph).unwrap();
+        assert_eq!(g.iter().collect::<Vec<_>>(), vec![(0, 1), (1, 0), (1, 2), (2, 1)]);
+    }
+
+    #[test]
+    fn conversions_fail_when_vertices_do_not_fit() {
+        let g = GraphMatrix::<u32>::from_edgelist(vec![(0, 255), (255, 0)]).unwrap();
+        let err = GraphMatrixError::Overflow {value: 255};
+        assert_eq!(Graph::<(), (), Directed, u8>::try_from(&g).unwrap_err(), err);
+        assert_eq!(Csr::<(), (), Directed, u8>::try_from(&g).unwrap_err(), err);
+        let g = GraphMatrix::<u32>::from_edgelist(vec![(0, 254)]).unwrap();
+        assert_eq!(Graph::<(), (), Directed, u8>::try_from(&g).unwrap().node_count(), 255);
+        assert_eq!(Csr::<(), (), Directed, u8>::try_from(&g).unwrap().node_count(), 255);
+    }
+}
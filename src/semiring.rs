@@ -0,0 +1,394 @@
+//! Semirings and sparse matrix-matrix multiplication.
+//!
+//! `mxm` multiplies two operands row by row (Gustavson's algorithm) under any `Semiring`. A
+//! plain GraphMatrix acts as a matrix whose stored elements all hold the semiring's `one`,
+//! so `mxm(&a, &a, &PlusTimes::new(), Some(MatrixMask::new(&a)))` counts, for every edge
+//! `(i, j)`, the paths `i -> k -> j` that close a triangle with it.
+
+use std::marker::PhantomData;
+
+use num::{Bounded, One, PrimInt, Zero};
+
+use crate::{from_usize, to_usize, GraphMatrix, GraphMatrixError, ValuedGraphMatrix};
+
+/// A semiring: an associative, commutative `add` with identity `zero`, and an associative
+/// `mul` with identity `one` that distributes over `add`.
+pub trait Semiring {
+    type Value: Clone;
+
+    fn zero(&self) -> Self::Value;
+    fn one(&self) -> Self::Value;
+    fn add(&self, a: Self::Value, b: Self::Value) -> Self::Value;
+    fn mul(&self, a: &Self::Value, b: &Self::Value) -> Self::Value;
+}
+
+/// The boolean semiring (`or`, `and`), for reachability.
+#[derive(Debug, Clone, Copy, Default)]
+pub struct Boolean;
+
+impl Semiring for Boolean {
+    type Value = bool;
+
+    fn zero(&self) -> bool {
+        false
+    }
+
+    fn one(&self) -> bool {
+        true
+    }
+
+    fn add(&self, a: bool, b: bool) -> bool {
+        a || b
+    }
+
+    fn mul(&self, a: &bool, b: &bool) -> bool {
+        *a && *b
+    }
+}
+
+/// The tropical semiring (`min`, `+`), for shortest paths. `zero` is the largest value of
+/// `V`, standing in for infinity.
+#[derive(Debug, Clone, Copy, Default)]
+pub struct MinPlus<V>(PhantomData<V>);
+
+impl<V> MinPlus<V> {
+    pub fn new() -> Self {
+        MinPlus(PhantomData)
+    }
+}
+
+impl<V> Semiring for MinPlus<V>
+where
+    V: Copy + PartialOrd + Bounded + Zero,
+{
+    type Value = V;
+
+    fn zero(&self) -> V {
+        V::max_value()
+    }
+
+    fn one(&self) -> V {
+        V::zero()
+    }
+
+    fn add(&self, a: V, b: V) -> V {
+        if b < a { b } else { a }
+    }
+
+    fn mul(&self, a: &V, b: &V) -> V {
+        *a + *b
+    }
+}
+
+/// The (`max`, `*`) semiring over non-negative values, for most-reliable paths.
+#[derive(Debug, Clone, Copy, Default)]
+pub struct MaxTimes<V>(PhantomData<V>);
+
+impl<V> MaxTimes<V> {
+    pub fn new() -> Self {
+        MaxTimes(PhantomData)
+    }
+}
+
+impl<V> Semiring for MaxTimes<V>
+where
+    V: Copy + PartialOrd + Zero + One,
+{
+    type Value = V;
+
+    fn zero(&self) -> V {
+        V::zero()
+    }
+
+    fn one(&self) -> V {
+        V::one()
+    }
+
+    fn add(&self, a: V, b: V) -> V {
+        if b > a { b } else { a }
+    }
+
+    fn mul(&self, a: &V, b: &V) -> V {
+        *a * *b
+    }
+}
+
+/// The ordinary (`+`, `*`) semiring, for counting paths.
+#[derive(Debug, Clone, Copy, Default)]
+pub struct PlusTimes<V>(PhantomData<V>);
+
+impl<V> PlusTimes<V> {
+    pub fn new() -> Self {
+        PlusTimes(PhantomData)
+    }
+}
+
+impl<V> Semiring for PlusTimes<V>
+where
+    V: Copy + Zero + One,
+{
+    type Value = V;
+
+    fn zero(&self) -> V {
+        V::zero()
+    }
+
+    fn one(&self) -> V {
+        V::one()
+    }
+
+    fn add(&self, a: V, b: V) -> V {
+        a + b
+    }
+
+    fn mul(&self, a: &V, b: &V) -> V {
+        *a * *b
+    }
+}
+
+/// A matrix that can be multiplied under a semiring whose values are `V`.
+pub trait Operand<T, V> {
+    /// The structure of the matrix.
+    fn structure(&self) -> &GraphMatrix<T>;
+    /// The value of the element at position `pos` of the structure's indices.
+    fn value(&self, pos: usize, one: &V) -> V;
+}
+
+impl<T, V: Clone> Operand<T, V> for GraphMatrix<T> {
+    fn structure(&self) -> &GraphMatrix<T> {
+        self
+    }
+
+    fn value(&self, _pos: usize, one: &V) -> V {
+        one.clone()
+    }
+}
+
+impl<T: PrimInt, V: Clone> Operand<T, V> for ValuedGraphMatrix<T, V> {
+    fn structure(&self) -> &GraphMatrix<T> {
+        self.graph()
+    }
+
+    fn value(&self, pos: usize, _one: &V) -> V {
+        self.values()[pos].clone()
+    }
+}
+
+/// Restricts which elements of an `mxm` result are computed. A complemented mask allows
+/// exactly the positions that are not stored in `m`.
+#[derive(Debug, Clone, Copy)]
+pub struct MatrixMask<'a, T> {
+    m: &'a GraphMatrix<T>,
+    complement: bool,
+}
+
+impl<'a, T> MatrixMask<'a, T> {
+    /// Allows the positions stored in `m`.
+    pub fn new(m: &'a GraphMatrix<T>) -> Self {
+        MatrixMask {m, complement: false}
+    }
+
+    /// Allows the positions not stored in `m`.
+    pub fn complement(m: &'a GraphMatrix<T>) -> Self {
+        MatrixMask {m, complement: true}
+    }
+}
+
+/// Computes `C = A·B` under `semiring`, restricted to the positions allowed by `mask`.
+pub fn mxm<T, S, A, B>(
+    a: &A,
+    b: &B,
+    semiring: &S,
+    mask: Option<MatrixMask<'_, T>>,
+) -> Result<ValuedGraphMatrix<T, S::Value>, GraphMatrixError>
+where
+    T: PrimInt,
+    S: Semiring,
+    A: Operand<T, S::Value>,
+    B: Operand<T, S::Value>,
+{
+    let (ga, gb) = (a.structure(), b.structure());
+    let (nrows, ninner) = ga.dims();
+    let (nb, ncols) = gb.dims();
+    if nb != ninner {
+        return Err(GraphMatrixError::DimensionMismatch {expected: ninner, found: nb})
+    }
+    if let Some(m) = mask {
+        let (mr, mc) = m.m.dims();
+        if mr != nrows {
+            return Err(GraphMatrixError::DimensionMismatch {expected: nrows, found: mr})
+        }
+        if mc != ncols {
+            return Err(GraphMatrixError::DimensionMismatch {expected: ncols, found: mc})
+        }
+    }
+
+    let one = semiring.one();
+    // marks[j] == i + 1 means column j is in the mask row for row i.
+    let mut marks: Vec<usize> = vec![0; if mask.is_some() { ncols } else { 0 }];
+    let mut acc: Vec<Option<S::Value>> = vec![None; ncols];
+    let mut touched: Vec<usize> = Vec::new();
+
+    let mut indptr: Vec<usize> = Vec::with_capacity(nrows + 1);
+    let mut indices: Vec<T> = Vec::new();
+    let mut values: Vec<S::Value> = Vec::new();
+    indptr.push(0);
+
+    for (i, wa) in ga.indptr.windows(2).enumerate() {
+        if let Some(m) = mask {
+            for &j in &m.m.indices[m.m.indptr[i]..m.m.indptr[i + 1]] {
+                marks[to_usize(j)?] = i + 1;
+            }
+        }
+        let allowed = |j: usize| match mask {
+            None => true,
+            Some(m) => (marks[j] == i + 1) != m.complement,
+        };
+        for pa in wa[0]..wa[1] {
+            let k = to_usize(ga.indices[pa])?;
+            let aik = a.value(pa, &one);
+            for pb in gb.indptr[k]..gb.indptr[k + 1] {
+                let j = to_usize(gb.indices[pb])?;
+                if !allowed(j) {
+                    continue;
+                }
+                let prod = semiring.mul(&aik, &b.value(pb, &one));
+                acc[j] = Some(match acc[j].take() {
+                    Some(v) => semiring.add(v, prod),
+                    None => {
+                        touched.push(j);
+                        prod
+                    }
+                });
+            }
+        }
+        touched.sort_unstable();
+        for &j in &touched {
+            indices.push(from_usize(j)?);
+            values.push(acc[j].take().expect("touched column holds a value"));
+        }
+        touched.clear();
+        indptr.push(indices.len());
+    }
+
+    let graph = GraphMatrix {indptr, indices, ncols};
+    ValuedGraphMatrix::try_from_parts(graph, values)
+}
+
+#[cfg(test)]
+mod tests {
+    use std::fmt::Debug;
+
+    use rand::rngs::StdRng;
+    use rand::{Rng, SeedableRng};
+
+    use super::*;
+
+    type Dense<V> = Vec<Vec<Option<V>>>;
+
+    /// A random `n` x `m` matrix in which each element is present with probability `p`.
+    fn random<V>(
+        n: usize,
+        m: usize,
+        p: f64,
+        rng: &mut StdRng,
+        mut value: impl FnMut(&mut StdRng) -> V,
+    ) -> ValuedGraphMatrix<u32, V> {
+        let mut edges = Vec::new();
+        for i in 0..n as u32 {
+            for j in 0..m as u32 {
+                if rng.gen_bool(p) {
+                    edges.push((i, j));
+                }
+            }
+        }
+        let g = GraphMatrix::from_edgelist_with_dims(edges, n, m).unwrap();
+        let values = (0..g.ne()).map(|_| value(rng)).collect();
+        ValuedGraphMatrix::try_from_parts(g, values).unwrap()
+    }
+
+    fn dense<V: Clone>(a: &ValuedGraphMatrix<u32, V>) -> Dense<V> {
+        let (n, m) = a.dims();
+        (0..n as u32).map(|i| (0..m as u32).map(|j| a.get(i, j).cloned()).collect()).collect()
+    }
+
+    /// The textbook triple loop, keeping only positions reached by at least one product.
+    fn dense_mxm<S: Semiring>(a: &Dense<S::Value>, b: &Dense<S::Value>, s: &S) -> Dense<S::Value> {
+        let ncols = b.first().map_or(0, Vec::len);
+        a.iter()
+            .map(|row| {
+                (0..ncols)
+                    .map(|j| {
+                        row.iter()
+                            .zip(b)
+                            .filter_map(|(x, brow)| Some(s.mul(x.as_ref()?, brow[j].as_ref()?)))
+                            .reduce(|x, y| s.add(x, y))
+                    })
+                    .collect()
+            })
+            .collect()
+    }
+
+    fn check<S>(a: &ValuedGraphMatrix<u32, S::Value>, b: &ValuedGraphMatrix<u32, S::Value>, s: &S)
+    where
+        S: Semiring,
+        S::Value: PartialEq + Debug,
+    {
+        let c = mxm(a, b, s, None).unwrap();
+        assert_eq!(dense(&c), dense_mxm(&dense(a), &dense(b), s));
+    }
+
+    #[test]
+    fn standard_semirings_match_a_dense_product() {
+        let mut rng = StdRng::seed_from_u64(8);
+        for p in [0.1, 0.3, 0.6] {
+            let small = |rng: &mut StdRng| rng.gen_range(0..10u64);
+            let (a, b) = (random(7, 5, p, &mut rng, small), random(5, 6, p, &mut rng, small));
+            check(&a, &b, &PlusTimes::new());
+            check(&a, &b, &MinPlus::new());
+            check(&a, &b, &MaxTimes::new());
+            let (a, b) = (random(7, 5, p, &mut rng, |_| true), random(5, 6, p, &mut rng, |_| true));
+            check(&a, &b, &Boolean);
+        }
+    }
+
+    #[test]
+    fn plain_operands_hold_one() {
+        let mut rng = StdRng::seed_from_u64(9);
+        let (a, b) = (random(6, 6, 0.4, &mut rng, |_| 1u32), random(6, 6, 0.4, &mut rng, |_| 1u32));
+        let plain = mxm(a.graph(), b.graph(), &PlusTimes::new(), None).unwrap();
+        let valued = mxm(&a, &b, &PlusTimes::new(), None).unwrap();
+        assert_eq!(dense(&plain), dense(&valued));
+    }
+
+    #[test]
+    fn masks_keep_or_drop_positions() {
+        let mut rng = StdRng::seed_from_u64(10);
+        let small = |rng: &mut StdRng| rng.gen_range(0..10u64);
+        let (a, b) = (random(8, 8, 0.3, &mut rng, small), random(8, 8, 0.3, &mut rng, small));
+        let m = random(8, 8, 0.5, &mut rng, |_| ());
+        let s = PlusTimes::new();
+        let full = dense(&mxm(&a, &b, &s, None).unwrap());
+        let kept = dense(&mxm(&a, &b, &s, Some(MatrixMask::new(m.graph()))).unwrap());
+        let dropped = dense(&mxm(&a, &b, &s, Some(MatrixMask::complement(m.graph()))).unwrap());
+        for i in 0..8 {
+            for j in 0..8 {
+                let in_mask = m.get(i as u32, j as u32).is_some();
+                assert_eq!(kept[i][j], if in_mask { full[i][j] } else { None });
+                assert_eq!(dropped[i][j], if in_mask { None } else { full[i][j] });
+            }
+        }
+    }
+
+    #[test]
+    fn rejects_mismatched_dimensions() {
+        let mut rng = StdRng::seed_from_u64(11);
+        let a = random(3, 4, 0.5, &mut rng, |_| 1u32);
+        let err = mxm(&a, &a, &PlusTimes::new(), None).unwrap_err();
+        assert_eq!(err, GraphMatrixError::DimensionMismatch {expected: 4, found: 3});
+        let b = random(4, 2, 0.5, &mut rng, |_| 1u32);
+        let m = random(3, 3, 0.5, &mut rng, |_| ());
+        let err = mxm(&a, &b, &PlusTimes::new(), Some(MatrixMask::new(m.graph()))).unwrap_err();
+        assert_eq!(err, GraphMatrixError::DimensionMismatch {expected: 2, found: 3});
+    }
+}
@@ -0,0 +1,139 @@
+use num::PrimInt;
+use serde::de::Error as _;
+use serde::{Deserialize, Deserializer, Serialize, Serializer};
+
+use crate::GraphMatrix;
+
+/// The owned counterpart of a serialized GraphMatrixView, checked before it becomes a
+/// GraphMatrix.
+#[derive(Deserialize)]
+#[serde(rename = "GraphMatrix")]
+struct GraphMatrixParts<T> {
+    indptr: Vec<usize>,
+    indices: Vec<T>,
+    ncols: usize,
+}
+
+impl<T> Serialize for GraphMatrix<T> where T: PrimInt + Serialize {
+    fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        self.view().serialize(serializer)
+    }
+}
+
+impl<'de, T> Deserialize<'de> for GraphMatrix<T> where T: PrimInt + Deserialize<'de> {
+    /// Rejects input that breaks the CSR invariants, as `try_from_csr_with_ncols` does.
+    fn deserialize<D: Deserializer<'de>>(deserializer: D) -> Result<Self, D::Error> {
+        let parts = GraphMatrixParts::deserialize(deserializer)?;
+        let GraphMatrixParts {indptr, indices, ncols} = parts;
+        GraphMatrix::try_from_csr_with_ncols(indptr, indices, ncols).map_err(D::Error::custom)
+    }
+}
+
+/// Serde support for `io::ErrorKind`, which has none itself. A kind is written as its
+/// description and read back by matching that against the stable kinds, so any other kind
+/// deserializes as `ErrorKind::Other`.
+pub(crate) mod error_kind {
+    use std::io::ErrorKind;
+
+    use serde::{Deserialize, Deserializer, Serializer};
+
+    const KINDS: [ErrorKind; 20] = [
+        ErrorKind::NotFound,
+        ErrorKind::PermissionDenied,
+        ErrorKind::ConnectionRefused,
+        ErrorKind::ConnectionReset,
+        ErrorKind::ConnectionAborted,
+        ErrorKind::NotConnected,
+        ErrorKind::AddrInUse,
+        ErrorKind::AddrNotAvailable,
+        ErrorKind::BrokenPipe,
+        ErrorKind::AlreadyExists,
+        ErrorKind::WouldBlock,
+        ErrorKind::InvalidInput,
+        ErrorKind::InvalidData,
+        ErrorKind::TimedOut,
+        ErrorKind::WriteZero,
+        ErrorKind::Interrupted,
+        ErrorKind::Unsupported,
+        ErrorKind::UnexpectedEof,
+        ErrorKind::OutOfMemory,
+        ErrorKind::Other,
+    ];
+
+    pub(crate) fn serialize<S: Serializer>(
+        kind: &ErrorKind,
+        serializer: S,
+    ) -> Result<S::Ok, S::Error> {
+        serializer.collect_str(kind)
+    }
+
+    pub(crate) fn deserialize<'de, D>(deserializer: D) -> Result<ErrorKind, D::Error>
+    where
+        D: Deserializer<'de>,
+    {
+        let description = String::deserialize(deserializer)?;
+        let kind = KINDS.into_iter().find(|k| k.to_string() == description);
+        Ok(kind.unwrap_or(ErrorKind::Other))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::{GraphMatrix, GraphMatrixError};
+
+    #[test]
+    fn graph_matrix_round_trip() {
+        let g = GraphMatrix::<u32>::from_edgelist(vec![(0, 1), (2, 0)]).unwrap();
+        let json = serde_json::to_string(&g).unwrap();
+        assert_eq!(json, r#"{"indptr":[0,1,1,2],"indices":[1,0],"ncols":3}"#);
+        let h: GraphMatrix<u32> = serde_json::from_str(&json).unwrap();
+        assert_eq!(h.dims(), g.dims());
+        assert_eq!(h.iter().collect::<Vec<_>>(), g.iter().collect::<Vec<_>>());
+    }
+
+    #[test]
+    fn views_serialize_like_matrices() {
+        let g = GraphMatrix::<u8>::from_edgelist_with_dims(vec![(1, 2), (1, 3)], 2, 4).unwrap();
+        let json = serde_json::to_string(&g.view()).unwrap();
+        assert_eq!(json, serde_json::to_string(&g).unwrap());
+        let h: GraphMatrix<u8> = serde_json::from_str(&json).unwrap();
+        assert_eq!(h.dims(), (2, 4));
+        assert_eq!(h.iter().collect::<Vec<_>>(), vec![(1, 2), (1, 3)]);
+    }
+
+    #[test]
+    fn errors_serialize_with_their_payload() {
+        let json = serde_json::to_string(&GraphMatrixError::Overflow {value: 300}).unwrap();
+        assert_eq!(json, r#"{"Overflow":{"value":300}}"#);
+        let io = std::io::Error::new(std::io::ErrorKind::NotFound, "no such file");
+        let json = serde_json::to_string(&GraphMatrixError::from(io)).unwrap();
+        assert_eq!(json, r#"{"Io":{"kind":"entity not found","message":"no such file"}}"#);
+    }
+
+    #[test]
+    fn graph_matrix_rejects_corrupt_structure() {
+        let bad = [
+            r#"{"indptr":[0,5,2],"indices":[0,1],"ncols":3}"#,
+            r#"{"indptr":[0,2,1,2],"indices":[0,1],"ncols":3}"#,
+            r#"{"indptr":[0,2],"indices":[1,0],"ncols":3}"#,
+            r#"{"indptr":[0,1],"indices":[3],"ncols":3}"#,
+        ];
+        for json in bad {
+            assert!(serde_json::from_str::<GraphMatrix<u32>>(json).is_err(), "{}", json);
+        }
+    }
+
+    #[test]
+    fn error_round_trip() {
+        let io = std::io::Error::new(std::io::ErrorKind::UnexpectedEof, "short read");
+        let errors = [
+            GraphMatrix::<u32>::try_from_csr(vec![0, 5, 2], vec![0, 1]).unwrap_err(),
+            GraphMatrixError::invalid_parameter("damping must be in [0, 1]"),
+            GraphMatrixError::from(io),
+        ];
+        for e in errors {
+            let json = serde_json::to_string(&e).unwrap();
+            assert_eq!(serde_json::from_str::<GraphMatrixError>(&json).unwrap(), e);
+        }
+    }
+}
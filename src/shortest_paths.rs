@@ -0,0 +1,405 @@
+//! Single-source shortest paths over a square GraphMatrix whose edge weights are given as a
+//! slice aligned with the matrix's indices, so `weights[i]` is the weight of the edge stored
+//! at `indices()[i]`. A ValuedGraphMatrix `v` supplies these as `v.graph()` and `v.values()`.
+
+use std::cmp::Ordering;
+use std::collections::{BTreeMap, BinaryHeap};
+use std::ops::Div;
+
+use num::{PrimInt, ToPrimitive, Zero};
+
+use crate::traversal::bfs;
+use crate::{from_usize, to_usize, Bitset, GraphMatrix, GraphMatrixError};
+
+/// Distances from the source and shortest-path-tree predecessors for every vertex.
+/// Unreachable vertices have neither; the source has a distance of zero and no predecessor.
+#[derive(Debug, Clone, PartialEq)]
+pub struct ShortestPaths<T, W> {
+    pub distances: Vec<Option<W>>,
+    pub predecessors: Vec<Option<T>>,
+}
+
+impl<T, W> ShortestPaths<T, W> where T: PrimInt, W: Clone {
+
+    fn new(n: usize) -> Self {
+        ShortestPaths {distances: vec![None; n], predecessors: vec![None; n]}
+    }
+
+    /// Returns the vertices on a shortest path from the source to `v`, inclusive, or `None`
+    /// if `v` is unreachable.
+    pub fn path_to(&self, v: T) -> Option<Vec<T>> {
+        let mut vu = v.to_usize()?;
+        self.distances.get(vu)?.as_ref()?;
+        let mut path = vec![v];
+        while let Some(p) = self.predecessors[vu] {
+            path.push(p);
+            vu = p.to_usize()?;
+        }
+        path.reverse();
+        Some(path)
+    }
+}
+
+pub(crate) fn check_weights<T, W>(
+    g: &GraphMatrix<T>,
+    weights: &[W],
+) -> Result<usize, GraphMatrixError>
+where
+    T: PrimInt,
+{
+    if weights.len() != g.ne() {
+        return Err(GraphMatrixError::DimensionMismatch {expected: g.ne(), found: weights.len()})
+    }
+    g.nv()
+}
+
+pub(crate) fn check_nonnegative<W>(weights: &[W]) -> Result<(), GraphMatrixError>
+where
+    W: PartialOrd + Zero,
+{
+    let nonnegative = |w: &W| {
+        matches!(w.partial_cmp(&W::zero()), Some(Ordering::Greater | Ordering::Equal))
+    };
+    match weights.iter().position(|w| !nonnegative(w)) {
+        Some(edge) => Err(GraphMatrixError::NegativeWeight {edge}),
+        None => Ok(()),
+    }
+}
+
+/// Shortest paths in an unweighted graph, where every edge has length one.
+pub fn unweighted<T: PrimInt>(
+    g: &GraphMatrix<T>,
+    source: T,
+) -> Result<ShortestPaths<T, usize>, GraphMatrixError> {
+    let res = bfs(g, source)?;
+    Ok(ShortestPaths {distances: res.distances, predecessors: res.parents})
+}
+
+// BinaryHeap is a max-heap and W may only be PartialOrd, so order entries by reversed
+// distance, treating incomparable values as equal.
+pub(crate) struct HeapEntry<W>(pub(crate) W, pub(crate) usize);
+
+impl<W: PartialOrd> PartialEq for HeapEntry<W> {
+    fn eq(&self, other: &Self) -> bool {
+        self.cmp(other) == Ordering::Equal
+    }
+}
+
+impl<W: PartialOrd> Eq for HeapEntry<W> {}
+
+impl<W: PartialOrd> PartialOrd for HeapEntry<W> {
+    fn partial_cmp(&self, other: &Self) -> Option<Ordering> {
+        Some(self.cmp(other))
+    }
+}
+
+impl<W: PartialOrd> Ord for HeapEntry<W> {
+    fn cmp(&self, other: &Self) -> Ordering {
+        other.0.partial_cmp(&self.0).unwrap_or(Ordering::Equal)
+    }
+}
+
+/// Dijkstra's algorithm with a binary heap. Returns `GraphMatrixError::NegativeWeight` if any
+/// weight is negative. Runs in O((V + E) log V).
+pub fn dijkstra<T, W>(
+    g: &GraphMatrix<T>,
+    weights: &[W],
+    source: T,
+) -> Result<ShortestPaths<T, W>, GraphMatrixError>
+where
+    T: PrimInt,
+    W: Copy + PartialOrd + Zero,
+{
+    let n = check_weights(g, weights)?;
+    check_nonnegative(weights)?;
+    let s = g.vertex(source)?;
+    let mut res = ShortestPaths::new(n);
+    let mut settled = Bitset::new(n);
+    let mut heap = BinaryHeap::new();
+    res.distances[s] = Some(W::zero());
+    heap.push(HeapEntry(W::zero(), s));
+
+    while let Some(HeapEntry(d, u)) = heap.pop() {
+        if !settled.insert(u) {
+            continue;
+        }
+        let tu = from_usize(u)?;
+        for (c, &w) in g.row_with_values(u, weights) {
+            let v = to_usize(c)?;
+            let nd = d + w;
+            if res.distances[v].is_none_or(|old| nd < old) {
+                res.distances[v] = Some(nd);
+                res.predecessors[v] = Some(tu);
+                heap.push(HeapEntry(nd, v));
+            }
+        }
+    }
+    Ok(res)
+}
+
+/// The Bellman-Ford algorithm, which allows negative weights. Returns
+/// `GraphMatrixError::NegativeCycle` with the offending cycle if one is reachable from
+/// `source`. Runs in O(VE), stopping early once a pass relaxes nothing.
+pub fn bellman_ford<T, W>(
+    g: &GraphMatrix<T>,
+    weights: &[W],
+    source: T,
+) -> Result<ShortestPaths<T, W>, GraphMatrixError>
+where
+    T: PrimInt,
+    W: Copy + PartialOrd + Zero,
+{
+    let n = check_weights(g, weights)?;
+    let s = g.vertex(source)?;
+    let mut res = ShortestPaths::new(n);
+    res.distances[s] = Some(W::zero());
+
+    // after n - 1 passes every shortest path is final, so a relaxation in pass n means a
+    // negative cycle.
+    let mut last_relaxed = None;
+    for _ in 0..n {
+        last_relaxed = None;
+        for u in 0..n {
+            let Some(d) = res.distances[u] else { continue };
+            let tu = from_usize(u)?;
+            for (c, &w) in g.row_with_values(u, weights) {
+                let v = to_usize(c)?;
+                let nd = d + w;
+                if res.distances[v].is_none_or(|old| nd < old) {
+                    res.distances[v] = Some(nd);
+                    res.predecessors[v] = Some(tu);
+                    last_relaxed = Some(v);
+                }
+            }
+        }
+        if last_relaxed.is_none() {
+            return Ok(res);
+        }
+    }
+    let v = last_relaxed.expect("the final pass relaxed an edge");
+    Err(negative_cycle(&res.predecessors, v, n))
+}
+
+/// Walks `n` predecessors back from `v`, which lands on a cycle if `v` was relaxed in the
+/// n-th pass, then collects that cycle in forward order.
+fn negative_cycle<T: PrimInt>(preds: &[Option<T>], v: usize, n: usize) -> GraphMatrixError {
+    let pred = |x: usize| preds[x].and_then(|p| p.to_usize());
+    let mut x = v;
+    for _ in 0..n {
+        match pred(x) {
+            Some(p) => x = p,
+            None => break,
+        }
+    }
+    let mut cycle = vec![x];
+    let mut y = pred(x);
+    while let Some(p) = y {
+        if p == x {
+            break;
+        }
+        cycle.push(p);
+        y = pred(p);
+    }
+    cycle.reverse();
+    GraphMatrixError::NegativeCycle {cycle}
+}
+
+/// Delta-stepping (Meyer and Sanders): vertices are kept in buckets of width `delta` by
+/// tentative distance, and each bucket is settled by relaxing light edges (weight at most
+/// `delta`) until it stops changing, then heavy edges once. A `delta` near the average edge
+/// weight keeps buckets small on large graphs; weights must be non-negative. Returns
+/// `GraphMatrixError::InvalidParameter` if `delta` is not positive.
+pub fn delta_stepping<T, W>(
+    g: &GraphMatrix<T>,
+    weights: &[W],
+    source: T,
+    delta: W,
+) -> Result<ShortestPaths<T, W>, GraphMatrixError>
+where
+    T: PrimInt,
+    W: Copy + PartialOrd + Zero + Div<Output = W> + ToPrimitive,
+{
+    let n = check_weights(g, weights)?;
+    check_nonnegative(weights)?;
+    if delta.partial_cmp(&W::zero()) != Some(Ordering::Greater) {
+        return Err(GraphMatrixError::invalid_parameter("delta must be positive"))
+    }
+    let s = g.vertex(source)?;
+    let bucket_of = |d: W| (d / delta).to_usize().unwrap_or(usize::MAX);
+
+    let mut res = ShortestPaths::new(n);
+    let mut buckets: BTreeMap<usize, Vec<usize>> = BTreeMap::new();
+    res.distances[s] = Some(W::zero());
+    buckets.entry(0).or_default().push(s);
+
+    let relax = |res: &mut ShortestPaths<T, W>,
+                     buckets: &mut BTreeMap<usize, Vec<usize>>,
+                     u: usize,
+                     light: bool|
+     -> Result<(), GraphMatrixError> {
+        let Some(d) = res.distances[u] else { return Ok(()) };
+        let tu = from_usize(u)?;
+        for (c, &w) in g.row_with_values(u, weights) {
+            if (w <= delta) != light {
+                continue;
+            }
+            let v = to_usize(c)?;
+            let nd = d + w;
+            if res.distances[v].is_none_or(|old| nd < old) {
+                res.distances[v] = Some(nd);
+                res.predecessors[v] = Some(tu);
+                buckets.entry(bucket_of(nd)).or_default().push(v);
+            }
+        }
+        Ok(())
+    };
+
+    let mut in_settled = Bitset::new(n);
+    while let Some(&i) = buckets.keys().next() {
+        let mut settled: Vec<usize> = Vec::new();
+        while let Some(bucket) = buckets.remove(&i) {
+            for u in bucket {
+                // skip entries left behind when a vertex moved to an earlier bucket.
+                if res.distances[u].map(bucket_of) != Some(i) {
+                    continue;
+                }
+                if in_settled.insert(u) {
+                    settled.push(u);
+                }
+                relax(&mut res, &mut buckets, u, true)?;
+            }
+        }
+        for &u in &settled {
+            relax(&mut res, &mut buckets, u, false)?;
+        }
+    }
+    Ok(res)
+}
+
+#[cfg(test)]
+mod tests {
+    use rand::rngs::StdRng;
+    use rand::{Rng, SeedableRng};
+
+    use super::*;
+
+    /// A random digraph with integer weights in `lo..hi`, aligned with its indices.
+    fn random_weighted(
+        n: u32,
+        m: usize,
+        lo: i64,
+        hi: i64,
+        seed: u64,
+    ) -> (GraphMatrix<u32>, Vec<i64>) {
+        let mut rng = StdRng::seed_from_u64(seed);
+        let edges = (0..m).map(|_| (rng.gen_range(0..n), rng.gen_range(0..n))).collect();
+        let g = GraphMatrix::from_edgelist_with_dims(edges, n as usize, n as usize).unwrap();
+        let weights = (0..g.ne()).map(|_| rng.gen_range(lo..hi)).collect();
+        (g, weights)
+    }
+
+    /// The weight of edge `(u, v)`.
+    fn weight_of(g: &GraphMatrix<u32>, weights: &[i64], u: usize, v: usize) -> i64 {
+        let pos = g.row_slice(u).binary_search(&(v as u32)).expect("edge exists");
+        weights[g.indptr()[u] + pos]
+    }
+
+    fn weighted() -> (GraphMatrix<u32>, Vec<f64>) {
+        let g = GraphMatrix::from_edgelist(vec![(0, 1), (0, 2), (1, 2), (2, 3)]).unwrap();
+        (g, vec![4.0, 1.0, 1.0, 2.5])
+    }
+
+    #[test]
+    fn delta_stepping_matches_dijkstra() {
+        let (g, w) = weighted();
+        let expected = dijkstra(&g, &w, 0).unwrap().distances;
+        for delta in [0.5, 1.0, 10.0] {
+            assert_eq!(delta_stepping(&g, &w, 0, delta).unwrap().distances, expected);
+        }
+    }
+
+    #[test]
+    fn delta_stepping_rejects_bad_delta() {
+        let (g, w) = weighted();
+        let err = GraphMatrixError::invalid_parameter("delta must be positive");
+        for delta in [0.0, -1.0, f64::NAN] {
+            assert_eq!(delta_stepping(&g, &w, 0, delta).unwrap_err(), err);
+        }
+    }
+
+    #[test]
+    fn algorithms_agree_on_non_negative_weights() {
+        for (seed, m) in [(1, 200), (2, 600), (3, 2000)] {
+            let (g, w) = random_weighted(150, m, 0, 20, seed);
+            let expected = dijkstra(&g, &w, 0).unwrap();
+            assert_eq!(bellman_ford(&g, &w, 0).unwrap().distances, expected.distances);
+            for delta in [1, 5, 50] {
+                let res = delta_stepping(&g, &w, 0, delta).unwrap();
+                assert_eq!(res.distances, expected.distances);
+            }
+            // every predecessor lies on a shortest path
+            for (v, p) in expected.predecessors.iter().enumerate() {
+                if let Some(p) = *p {
+                    let (dp, dv) = (expected.distances[p as usize], expected.distances[v]);
+                    assert_eq!(dp.map(|d| d + weight_of(&g, &w, p as usize, v)), dv);
+                }
+            }
+        }
+    }
+
+    #[test]
+    fn unweighted_counts_edges() {
+        let (g, _) = random_weighted(100, 300, 0, 1, 4);
+        let ones = vec![1usize; g.ne()];
+        assert_eq!(unweighted(&g, 0).unwrap().distances, dijkstra(&g, &ones, 0).unwrap().distances);
+    }
+
+    #[test]
+    fn path_to_handles_the_source_and_unreachable_vertices() {
+        let (g, w) = weighted();
+        let res = dijkstra(&g, &w, 1).unwrap();
+        assert_eq!(res.path_to(1), Some(vec![1]));
+        assert_eq!(res.path_to(3), Some(vec![1, 2, 3]));
+        assert_eq!(res.path_to(0), None);
+        assert_eq!(res.path_to(4), None);
+        let res = dijkstra(&g, &w, 0).unwrap();
+        assert_eq!(res.path_to(2), Some(vec![0, 2]));
+        assert_eq!(res.distances[3], Some(3.5));
+    }
+
+    #[test]
+    fn negative_weights_are_located() {
+        let (g, _) = weighted();
+        let err = GraphMatrixError::NegativeWeight {edge: 2};
+        assert_eq!(dijkstra(&g, &[4.0, 1.0, -1.0, 2.5], 0).unwrap_err(), err);
+        assert_eq!(delta_stepping(&g, &[4.0, 1.0, f64::NAN, 2.5], 0, 1.0).unwrap_err(), err);
+        let res = bellman_ford(&g, &[4.0, 1.0, -5.0, 2.5], 0).unwrap();
+        assert_eq!(res.distances, vec![Some(0.0), Some(4.0), Some(-1.0), Some(1.5)]);
+        assert_eq!(res.path_to(3), Some(vec![0, 1, 2, 3]));
+    }
+
+    #[test]
+    fn negative_cycles_are_real() {
+        let mut found = 0;
+        for seed in 0..20 {
+            let (g, w) = random_weighted(30, 60, -4, 10, seed);
+            let Err(err) = bellman_ford(&g, &w, 0) else { continue };
+            found += 1;
+            let GraphMatrixError::NegativeCycle {cycle} = err else {
+                panic!("unexpected error {:?}", err)
+            };
+            let total: i64 = (0..cycle.len())
+                .map(|i| weight_of(&g, &w, cycle[i], cycle[(i + 1) % cycle.len()]))
+                .sum();
+            assert!(total < 0, "cycle {:?} has weight {}", cycle, total);
+        }
+        assert!(found > 0);
+        let g = GraphMatrix::<u32>::from_edgelist(vec![(0, 1), (1, 2), (2, 3), (3, 1), (3, 4)])
+            .unwrap();
+        let err = bellman_ford(&g, &[1, -2, 1, -1, 1], 0).unwrap_err();
+        let GraphMatrixError::NegativeCycle {mut cycle} = err else { panic!("{:?}", err) };
+        let start = cycle.iter().position(|&v| v == 1).unwrap();
+        cycle.rotate_left(start);
+        assert_eq!(cycle, vec![1, 2, 3]);
+    }
+}
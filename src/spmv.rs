@@ -0,0 +1,194 @@
+//! Boolean sparse matrix-vector products over a GraphMatrix.
+//!
+//! `vxm` computes `u·A` by pushing: every vertex in the frontier `u` marks the columns in its
+//! row. `mxv` computes `A·u` by pulling: every row checks whether any of its columns is in
+//! `u`, stopping at the first hit. On a graph with adjacency matrix `A`, a top-down BFS step
+//! is `vxm(frontier, A)` and a bottom-up step is `mxv(Aᵀ, frontier)`.
+
+use std::borrow::Cow;
+
+use num::PrimInt;
+
+use crate::{from_usize, index, to_usize, Bitset, GraphMatrix, GraphMatrixError};
+
+/// An input vector, given either as a bitset or as a list of set positions.
+#[derive(Debug, Clone, Copy)]
+pub enum Frontier<'a, T> {
+    Dense(&'a Bitset),
+    Sparse(&'a [T]),
+}
+
+/// Restricts which positions of an output vector may be set. A complemented mask allows
+/// exactly the positions that are clear in `bits`.
+#[derive(Debug, Clone, Copy)]
+pub struct Mask<'a> {
+    bits: &'a Bitset,
+    complement: bool,
+}
+
+impl<'a> Mask<'a> {
+    /// Allows the positions set in `bits`.
+    pub fn new(bits: &'a Bitset) -> Self {
+        Mask {bits, complement: false}
+    }
+
+    /// Allows the positions clear in `bits`.
+    pub fn complement(bits: &'a Bitset) -> Self {
+        Mask {bits, complement: true}
+    }
+
+    pub fn allows(&self, i: usize) -> bool {
+        self.bits.contains(i) != self.complement
+    }
+
+    fn check_len(&self, n: usize) -> Result<(), GraphMatrixError> {
+        check_len(self.bits.len(), n)
+    }
+}
+
+fn check_len(found: usize, expected: usize) -> Result<(), GraphMatrixError> {
+    if found != expected {
+        return Err(GraphMatrixError::DimensionMismatch {expected, found})
+    }
+    Ok(())
+}
+
+/// Returns the frontier as a bitset of length `n`, copying only if it is sparse.
+fn densify<T: PrimInt>(
+    u: Frontier<'_, T>,
+    n: usize,
+) -> Result<Cow<'_, Bitset>, GraphMatrixError> {
+    match u {
+        Frontier::Dense(b) => {
+            check_len(b.len(), n)?;
+            Ok(Cow::Borrowed(b))
+        }
+        Frontier::Sparse(s) => {
+            let mut b = Bitset::new(n);
+            for &v in s {
+                let vu = to_usize(v)?;
+                if vu >= n {
+                    return Err(GraphMatrixError::BoundsError {index: vu, bound: n, edge: None})
+                }
+                b.insert(vu);
+            }
+            Ok(Cow::Owned(b))
+        }
+    }
+}
+
+/// Computes `w = u·A` over the boolean semiring, where `u` has one entry per row of `a`.
+/// Only positions allowed by `mask` are set in `w`.
+pub fn vxm<T: PrimInt>(
+    u: Frontier<'_, T>,
+    a: &GraphMatrix<T>,
+    mask: Option<&Mask<'_>>,
+) -> Result<Bitset, GraphMatrixError> {
+    let (nrows, ncols) = a.dims();
+    if let Some(m) = mask {
+        m.check_len(ncols)?;
+    }
+    let mut w = Bitset::new(ncols);
+    let mut push = |r: T| -> Result<(), GraphMatrixError> {
+        for &c in a.row(r)? {
+            let cu = to_usize(c)?;
+            if mask.is_none_or(|m| m.allows(cu)) {
+                w.insert(cu);
+            }
+        }
+        Ok(())
+    };
+    match u {
+        Frontier::Dense(b) => {
+            check_len(b.len(), nrows)?;
+            for r in b.iter() {
+                push(from_usize(r)?)?;
+            }
+        }
+        Frontier::Sparse(s) => {
+            for &r in s {
+                push(r)?;
+            }
+        }
+    }
+    Ok(w)
+}
+
+/// Computes `w = A·u` over the boolean semiring, where `u` has one entry per column of `a`.
+/// Rows not allowed by `mask` are skipped entirely.
+pub fn mxv<T: PrimInt>(
+    a: &GraphMatrix<T>,
+    u: Frontier<'_, T>,
+    mask: Option<&Mask<'_>>,
+) -> Result<Bitset, GraphMatrixError> {
+    let (nrows, ncols) = a.dims();
+    if let Some(m) = mask {
+        m.check_len(nrows)?;
+    }
+    let u = densify(u, ncols)?;
+    let mut w = Bitset::new(nrows);
+    for (r, win) in a.indptr.windows(2).enumerate() {
+        if mask.is_some_and(|m| !m.allows(r)) {
+            continue;
+        }
+        let hit = a.indices[win[0]..win[1]].iter().any(|&c| u.contains(index(c)));
+        if hit {
+            w.insert(r);
+        }
+    }
+    Ok(w)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn sample() -> GraphMatrix<u32> {
+        GraphMatrix::from_edgelist(vec![(0, 1), (0, 2), (1, 3), (2, 3), (3, 0), (4, 4)]).unwrap()
+    }
+
+    fn set(n: usize, bits: &[usize]) -> Bitset {
+        Bitset::from_indices(n, bits.iter().copied())
+    }
+
+    #[test]
+    fn vxm_pushes_along_rows() {
+        let a = sample();
+        let expected = set(5, &[0, 1, 2]);
+        assert_eq!(vxm(Frontier::Dense(&set(5, &[0, 3])), &a, None).unwrap(), expected);
+        assert_eq!(vxm(Frontier::Sparse(&[3, 0]), &a, None).unwrap(), expected);
+    }
+
+    #[test]
+    fn mxv_pulls_from_columns() {
+        let a = sample();
+        let expected = set(5, &[1, 2, 4]);
+        assert_eq!(mxv(&a, Frontier::Dense(&set(5, &[3, 4])), None).unwrap(), expected);
+        assert_eq!(mxv(&a, Frontier::Sparse(&[4, 3]), None).unwrap(), expected);
+    }
+
+    #[test]
+    fn masks_restrict_the_output() {
+        let a = sample();
+        let bits = set(5, &[1, 3]);
+        let (keep, drop) = (Mask::new(&bits), Mask::complement(&bits));
+        let u = set(5, &[0, 3]);
+        assert_eq!(vxm(Frontier::Dense(&u), &a, Some(&keep)).unwrap(), set(5, &[1]));
+        assert_eq!(vxm(Frontier::Dense(&u), &a, Some(&drop)).unwrap(), set(5, &[0, 2]));
+        let u = set(5, &[3, 4]);
+        assert_eq!(mxv(&a, Frontier::Dense(&u), Some(&keep)).unwrap(), set(5, &[1]));
+        assert_eq!(mxv(&a, Frontier::Dense(&u), Some(&drop)).unwrap(), set(5, &[2, 4]));
+    }
+
+    #[test]
+    fn rectangular_products_take_their_lengths_from_the_matrix() {
+        let a = GraphMatrix::<u32>::from_edgelist_with_dims(vec![(0, 3), (1, 0), (1, 2)], 2, 4)
+            .unwrap();
+        assert_eq!(vxm(Frontier::Sparse(&[1]), &a, None).unwrap(), set(4, &[0, 2]));
+        assert_eq!(mxv(&a, Frontier::Sparse(&[3]), None).unwrap(), set(2, &[0]));
+        let err = vxm(Frontier::Dense(&Bitset::new(4)), &a, None);
+        assert_eq!(err.unwrap_err(), GraphMatrixError::DimensionMismatch {expected: 2, found: 4});
+        let err = mxv(&a, Frontier::Sparse(&[4]), None).unwrap_err();
+        assert_eq!(err, GraphMatrixError::BoundsError {index: 4, bound: 4, edge: None});
+    }
+}
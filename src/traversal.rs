@@ -0,0 +1,10 @@
+//! Graph traversals over a square GraphMatrix, treating row `r` as the out-neighbors of
+//! vertex `r`.
+
+mod bfs;
+mod dfs;
+
+pub use bfs::{bfs, direction_optimizing_bfs, multi_source_bfs, Bfs, BfsResult, BfsStep};
+pub use dfs::{
+    dfs, dfs_all, Control, DfsEvent, DfsEvents, DfsPostorder, DfsPreorder, DfsVisitor,
+};
@@ -0,0 +1,267 @@
+use std::collections::VecDeque;
+
+use num::PrimInt;
+
+use crate::{from_usize, to_usize, BiGraphMatrix, Bitset, GraphMatrix, GraphMatrixError};
+
+/// A vertex reached by a breadth-first search.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct BfsStep<T> {
+    pub vertex: T,
+    /// The vertex it was discovered from, or `None` for a source.
+    pub parent: Option<T>,
+    /// The number of edges between it and the nearest source.
+    pub depth: usize,
+}
+
+/// Distances and BFS-tree parents for every vertex. Unreached vertices have neither;
+/// sources have a distance of zero and no parent.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct BfsResult<T> {
+    pub distances: Vec<Option<usize>>,
+    pub parents: Vec<Option<T>>,
+}
+
+impl<T> BfsResult<T> where T: PrimInt {
+
+    fn new(n: usize) -> Self {
+        BfsResult {distances: vec![None; n], parents: vec![None; n]}
+    }
+
+    /// Returns the vertices on the BFS-tree path from a source to `v`, inclusive, or `None`
+    /// if `v` was not reached.
+    pub fn path_to(&self, v: T) -> Option<Vec<T>> {
+        let mut vu = v.to_usize()?;
+        self.distances.get(vu).copied().flatten()?;
+        let mut path = vec![v];
+        while let Some(p) = self.parents[vu] {
+            path.push(p);
+            vu = p.to_usize()?;
+        }
+        path.reverse();
+        Some(path)
+    }
+}
+
+/// A lazy breadth-first search. Vertices are yielded in order of discovery, so the search
+/// can be abandoned as soon as the caller has seen enough.
+#[derive(Debug, Clone)]
+pub struct Bfs<'a, T> {
+    g: &'a GraphMatrix<T>,
+    visited: Bitset,
+    queue: VecDeque<BfsStep<T>>,
+}
+
+impl<'a, T> Bfs<'a, T> where T: PrimInt {
+
+    pub fn new(g: &'a GraphMatrix<T>, source: T) -> Result<Self, GraphMatrixError> {
+        Self::multi_source(g, &[source])
+    }
+
+    /// Starts from every vertex in `sources` at once; each is yielded at depth zero.
+    pub fn multi_source(g: &'a GraphMatrix<T>, sources: &[T]) -> Result<Self, GraphMatrixError> {
+        let mut visited = Bitset::new(g.nv()?);
+        let mut queue = VecDeque::new();
+        for &s in sources {
+            if visited.insert(g.vertex(s)?) {
+                queue.push_back(BfsStep {vertex: s, parent: None, depth: 0});
+            }
+        }
+        Ok(Bfs {g, visited, queue})
+    }
+}
+
+impl<'a, T> Iterator for Bfs<'a, T> where T: PrimInt {
+    type Item = BfsStep<T>;
+    fn next(&mut self) -> Option<BfsStep<T>> {
+        let step = self.queue.pop_front()?;
+        // every vertex in the queue came from the matrix, so it converts back.
+        let vu = step.vertex.to_usize()?;
+        for &n in self.g.row_slice(vu) {
+            if self.visited.insert(n.to_usize()?) {
+                let depth = step.depth + 1;
+                self.queue.push_back(BfsStep {vertex: n, parent: Some(step.vertex), depth});
+            }
+        }
+        Some(step)
+    }
+}
+
+impl<'a, T> std::iter::FusedIterator for Bfs<'a, T> where T: PrimInt {}
+
+/// Runs a breadth-first search from `source`, returning distances and parents.
+pub fn bfs<T: PrimInt>(g: &GraphMatrix<T>, source: T) -> Result<BfsResult<T>, GraphMatrixError> {
+    multi_source_bfs(g, &[source])
+}
+
+/// Runs a breadth-first search from every vertex in `sources` at once, so each vertex's
+/// distance is to its nearest source.
+pub fn multi_source_bfs<T: PrimInt>(
+    g: &GraphMatrix<T>,
+    sources: &[T],
+) -> Result<BfsResult<T>, GraphMatrixError> {
+    let mut res = BfsResult::new(g.nv()?);
+    for step in Bfs::multi_source(g, sources)? {
+        let vu = to_usize(step.vertex)?;
+        res.distances[vu] = Some(step.depth);
+        res.parents[vu] = step.parent;
+    }
+    Ok(res)
+}
+
+// Switching thresholds from Beamer, Asanović and Patterson, "Direction-Optimizing
+// Breadth-First Search" (SC '12).
+const ALPHA: usize = 15;
+const BETA: usize = 18;
+
+/// Runs a breadth-first search from `source` that switches between top-down steps (pushing
+/// along out-edges of the frontier) and bottom-up steps (each unvisited vertex scanning its
+/// in-edges for a frontier vertex) depending on which is expected to touch fewer edges.
+/// Produces the same distances as `bfs`; parents may differ where several are valid.
+pub fn direction_optimizing_bfs<T: PrimInt>(
+    g: &BiGraphMatrix<T>,
+    source: T,
+) -> Result<BfsResult<T>, GraphMatrixError> {
+    let (fwd, bwd) = (g.forward(), g.backward());
+    let n = fwd.nv()?;
+    let s = fwd.vertex(source)?;
+    let mut res = BfsResult::new(n);
+    res.distances[s] = Some(0);
+
+    let mut frontier: Vec<usize> = vec![s];
+    // edges out of unvisited vertices, for the top-down -> bottom-up heuristic.
+    let mut unexplored_edges = fwd.ne() - fwd.row_slice(s).len();
+    let mut bottom_up = false;
+    let mut depth = 0;
+
+    while !frontier.is_empty() {
+        let frontier_edges: usize = frontier.iter().map(|&v| fwd.row_slice(v).len()).sum();
+        if !bottom_up && frontier_edges > unexplored_edges / ALPHA {
+            bottom_up = true;
+        } else if bottom_up && frontier.len() < n / BETA {
+            bottom_up = false;
+        }
+        depth += 1;
+
+        let mut next: Vec<usize> = Vec::new();
+        if bottom_up {
+            let in_frontier = Bitset::from_indices(n, frontier.iter().copied());
+            for v in 0..n {
+                if res.distances[v].is_some() {
+                    continue;
+                }
+                for &p in bwd.row_slice(v) {
+                    let pu = to_usize(p)?;
+                    if in_frontier.contains(pu) {
+                        res.distances[v] = Some(depth);
+                        res.parents[v] = Some(p);
+                        next.push(v);
+                        break;
+                    }
+                }
+            }
+        } else {
+            for &u in &frontier {
+                let tu = from_usize(u)?;
+                for &c in fwd.row_slice(u) {
+                    let cu = to_usize(c)?;
+                    if res.distances[cu].is_none() {
+                        res.distances[cu] = Some(depth);
+                        res.parents[cu] = Some(tu);
+                        next.push(cu);
+                    }
+                }
+            }
+        }
+        unexplored_edges -= next.iter().map(|&v| fwd.row_slice(v).len()).sum::<usize>();
+        frontier = next;
+    }
+    Ok(res)
+}
+
+#[cfg(test)]
+mod tests {
+    use rand::rngs::StdRng;
+    use rand::{Rng, SeedableRng};
+
+    use super::*;
+
+    fn random_graph(n: u32, m: usize, seed: u64) -> GraphMatrix<u32> {
+        let mut rng = StdRng::seed_from_u64(seed);
+        let edges = (0..m).map(|_| (rng.gen_range(0..n), rng.gen_range(0..n))).collect();
+        GraphMatrix::from_edgelist_with_dims(edges, n as usize, n as usize).unwrap()
+    }
+
+    /// Checks that every reached vertex other than a source has a parent one step closer,
+    /// with an edge from the parent to it.
+    fn check_tree(g: &GraphMatrix<u32>, res: &BfsResult<u32>) {
+        for (v, d) in res.distances.iter().enumerate() {
+            match (d, res.parents[v]) {
+                (Some(0) | None, None) => {}
+                (Some(d), Some(p)) => {
+                    assert_eq!(res.distances[p as usize], Some(d - 1));
+                    assert!(g.has_index(p, v as u32).unwrap());
+                }
+                other => panic!("vertex {} has distance and parent {:?}", v, other),
+            }
+        }
+    }
+
+    #[test]
+    fn bfs_on_a_small_graph() {
+        let edges = vec![(0, 1), (0, 2), (1, 3), (2, 3), (3, 4), (5, 0)];
+        let g = GraphMatrix::<u32>::from_edgelist(edges).unwrap();
+        let res = bfs(&g, 0).unwrap();
+        assert_eq!(res.distances, vec![Some(0), Some(1), Some(1), Some(2), Some(3), None]);
+        assert_eq!(res.path_to(4), Some(vec![0, 1, 3, 4]));
+        assert_eq!(res.path_to(0), Some(vec![0]));
+        assert_eq!(res.path_to(5), None);
+        assert_eq!(res.path_to(6), None);
+        check_tree(&g, &res);
+    }
+
+    #[test]
+    fn iterator_follows_bfs_order() {
+        for seed in 0..5 {
+            let g = random_graph(200, 400, seed);
+            let steps: Vec<BfsStep<u32>> = Bfs::new(&g, 0).unwrap().collect();
+            let res = bfs(&g, 0).unwrap();
+            assert_eq!(steps.len(), res.distances.iter().flatten().count());
+            assert!(steps.windows(2).all(|w| w[0].depth <= w[1].depth));
+            for step in &steps {
+                assert_eq!(res.distances[step.vertex as usize], Some(step.depth));
+                assert_eq!(res.parents[step.vertex as usize], step.parent);
+            }
+        }
+    }
+
+    #[test]
+    fn multi_source_distances_are_to_the_nearest_source() {
+        let g = random_graph(200, 300, 7);
+        let sources = [3, 50, 3, 120];
+        let res = multi_source_bfs(&g, &sources).unwrap();
+        let singles: Vec<BfsResult<u32>> = sources.iter().map(|&s| bfs(&g, s).unwrap()).collect();
+        for v in 0..200 {
+            let nearest = singles.iter().filter_map(|r| r.distances[v]).min();
+            assert_eq!(res.distances[v], nearest);
+        }
+        check_tree(&g, &res);
+        let steps: Vec<BfsStep<u32>> = Bfs::multi_source(&g, &sources).unwrap().collect();
+        assert_eq!(&steps[..3].iter().map(|s| s.vertex).collect::<Vec<_>>(), &[3, 50, 120]);
+        for step in &steps {
+            assert_eq!(res.distances[step.vertex as usize], Some(step.depth));
+        }
+    }
+
+    #[test]
+    fn direction_optimizing_bfs_matches_bfs() {
+        for (seed, m) in [(1, 300), (2, 1000), (3, 4000)] {
+            let g = random_graph(300, m, seed);
+            let expected = bfs(&g, 0).unwrap();
+            let bi = BiGraphMatrix::new(random_graph(300, m, seed)).unwrap();
+            let res = direction_optimizing_bfs(&bi, 0).unwrap();
+            assert_eq!(res.distances, expected.distances);
+            check_tree(&g, &res);
+        }
+    }
+}
@@ -0,0 +1,334 @@
+use std::ops::Range;
+
+use num::PrimInt;
+
+use crate::{Bitset, GraphMatrix, GraphMatrixError};
+
+/// Whether a depth-first search should keep going after a visitor callback.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Control {
+    Continue,
+    Stop,
+}
+
+/// Something that happened during a depth-first search. Edges are classified relative to
+/// the DFS forest: a tree edge discovers a new vertex, a back edge leads to a vertex still on
+/// the stack (closing a cycle), a forward edge leads to an already-finished descendant, and
+/// a cross edge leads to an already-finished vertex in another subtree.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum DfsEvent<T> {
+    Discover(T),
+    TreeEdge(T, T),
+    BackEdge(T, T),
+    ForwardEdge(T, T),
+    CrossEdge(T, T),
+    Finish(T),
+}
+
+/// Callbacks for `dfs` and `dfs_all`. Every method defaults to doing nothing; returning
+/// `Control::Stop` ends the search.
+pub trait DfsVisitor<T> {
+    fn discover(&mut self, _v: T) -> Control {
+        Control::Continue
+    }
+
+    fn finish(&mut self, _v: T) -> Control {
+        Control::Continue
+    }
+
+    fn tree_edge(&mut self, _u: T, _v: T) -> Control {
+        Control::Continue
+    }
+
+    fn back_edge(&mut self, _u: T, _v: T) -> Control {
+        Control::Continue
+    }
+
+    fn forward_edge(&mut self, _u: T, _v: T) -> Control {
+        Control::Continue
+    }
+
+    fn cross_edge(&mut self, _u: T, _v: T) -> Control {
+        Control::Continue
+    }
+}
+
+const UNSEEN: usize = usize::MAX;
+
+/// A lazy, non-recursive depth-first search that yields every `DfsEvent` in order. Neighbors
+/// are explored in increasing order, and stack depth is bounded only by available memory.
+#[derive(Debug, Clone)]
+pub struct DfsEvents<'a, T> {
+    g: &'a GraphMatrix<T>,
+    roots: Range<usize>,
+    // discovery order of each vertex, or UNSEEN.
+    disc: Vec<usize>,
+    finished: Bitset,
+    time: usize,
+    // (vertex, position of its next unexplored edge)
+    stack: Vec<(usize, usize)>,
+    pending: Option<DfsEvent<T>>,
+}
+
+impl<'a, T> DfsEvents<'a, T> where T: PrimInt {
+
+    /// Searches the vertices reachable from `source`.
+    pub fn new(g: &'a GraphMatrix<T>, source: T) -> Result<Self, GraphMatrixError> {
+        let s = g.vertex(source)?;
+        Self::with_roots(g, s..s + 1)
+    }
+
+    /// Searches the whole graph, starting a new tree at each unvisited vertex in order.
+    pub fn all(g: &'a GraphMatrix<T>) -> Result<Self, GraphMatrixError> {
+        Self::with_roots(g, 0..g.nv()?)
+    }
+
+    fn with_roots(g: &'a GraphMatrix<T>, roots: Range<usize>) -> Result<Self, GraphMatrixError> {
+        let n = g.nv()?;
+        Ok(DfsEvents {
+            g,
+            roots,
+            disc: vec![UNSEEN; n],
+            finished: Bitset::new(n),
+            time: 0,
+            stack: Vec::new(),
+            pending: None,
+        })
+    }
+
+    fn vertex(v: usize) -> T {
+        // every vertex on the stack is a row of a square matrix indexed by T.
+        T::from(v).expect("vertex fits in T")
+    }
+
+    fn enter(&mut self, v: usize) {
+        self.disc[v] = self.time;
+        self.time += 1;
+        self.stack.push((v, self.g.indptr[v]));
+    }
+}
+
+impl<'a, T> Iterator for DfsEvents<'a, T> where T: PrimInt {
+    type Item = DfsEvent<T>;
+    fn next(&mut self) -> Option<DfsEvent<T>> {
+        if let Some(e) = self.pending.take() {
+            return Some(e);
+        }
+        loop {
+            let Some(&mut (u, ref mut pos)) = self.stack.last_mut() else {
+                let r = self.roots.next()?;
+                if self.disc[r] == UNSEEN {
+                    self.enter(r);
+                    return Some(DfsEvent::Discover(Self::vertex(r)));
+                }
+                continue;
+            };
+            if *pos == self.g.indptr[u + 1] {
+                self.stack.pop();
+                self.finished.insert(u);
+                return Some(DfsEvent::Finish(Self::vertex(u)));
+            }
+            let v = self.g.indices[*pos];
+            *pos += 1;
+            let (tu, vu) = (Self::vertex(u), v.to_usize()?);
+            return Some(if self.disc[vu] == UNSEEN {
+                self.enter(vu);
+                self.pending = Some(DfsEvent::Discover(v));
+                DfsEvent::TreeEdge(tu, v)
+            } else if !self.finished.contains(vu) {
+                DfsEvent::BackEdge(tu, v)
+            } else if self.disc[u] < self.disc[vu] {
+                DfsEvent::ForwardEdge(tu, v)
+            } else {
+                DfsEvent::CrossEdge(tu, v)
+            });
+        }
+    }
+}
+
+impl<'a, T> std::iter::FusedIterator for DfsEvents<'a, T> where T: PrimInt {}
+
+/// Yields vertices reachable from a source in depth-first pre-order (discovery order).
+#[derive(Debug, Clone)]
+pub struct DfsPreorder<'a, T>(DfsEvents<'a, T>);
+
+impl<'a, T> DfsPreorder<'a, T> where T: PrimInt {
+    pub fn new(g: &'a GraphMatrix<T>, source: T) -> Result<Self, GraphMatrixError> {
+        Ok(DfsPreorder(DfsEvents::new(g, source)?))
+    }
+}
+
+impl<'a, T> Iterator for DfsPreorder<'a, T> where T: PrimInt {
+    type Item = T;
+    fn next(&mut self) -> Option<T> {
+        self.0.find_map(|e| match e {
+            DfsEvent::Discover(v) => Some(v),
+            _ => None,
+        })
+    }
+}
+
+/// Yields vertices reachable from a source in depth-first post-order (finishing order).
+#[derive(Debug, Clone)]
+pub struct DfsPostorder<'a, T>(DfsEvents<'a, T>);
+
+impl<'a, T> DfsPostorder<'a, T> where T: PrimInt {
+    pub fn new(g: &'a GraphMatrix<T>, source: T) -> Result<Self, GraphMatrixError> {
+        Ok(DfsPostorder(DfsEvents::new(g, source)?))
+    }
+}
+
+impl<'a, T> Iterator for DfsPostorder<'a, T> where T: PrimInt {
+    type Item = T;
+    fn next(&mut self) -> Option<T> {
+        self.0.find_map(|e| match e {
+            DfsEvent::Finish(v) => Some(v),
+            _ => None,
+        })
+    }
+}
+
+fn drive<T, V>(events: DfsEvents<'_, T>, visitor: &mut V) -> Control
+where
+    T: PrimInt,
+    V: DfsVisitor<T> + ?Sized,
+{
+    for e in events {
+        let c = match e {
+            DfsEvent::Discover(v) => visitor.discover(v),
+            DfsEvent::TreeEdge(u, v) => visitor.tree_edge(u, v),
+            DfsEvent::BackEdge(u, v) => visitor.back_edge(u, v),
+            DfsEvent::ForwardEdge(u, v) => visitor.forward_edge(u, v),
+            DfsEvent::CrossEdge(u, v) => visitor.cross_edge(u, v),
+            DfsEvent::Finish(v) => visitor.finish(v),
+        };
+        if c == Control::Stop {
+            return Control::Stop;
+        }
+    }
+    Control::Continue
+}
+
+/// Runs a depth-first search from `source`, reporting events to `visitor`. Returns
+/// `Control::Stop` if the visitor ended the search early.
+pub fn dfs<T, V>(
+    g: &GraphMatrix<T>,
+    source: T,
+    visitor: &mut V,
+) -> Result<Control, GraphMatrixError>
+where
+    T: PrimInt,
+    V: DfsVisitor<T> + ?Sized,
+{
+    Ok(drive(DfsEvents::new(g, source)?, visitor))
+}
+
+/// Runs a depth-first search over the whole graph, rooting a new tree at each vertex not yet
+/// visited, in increasing order.
+pub fn dfs_all<T, V>(g: &GraphMatrix<T>, visitor: &mut V) -> Result<Control, GraphMatrixError>
+where
+    T: PrimInt,
+    V: DfsVisitor<T> + ?Sized,
+{
+    Ok(drive(DfsEvents::all(g)?, visitor))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// Records every event, stopping once `stop_at` is discovered.
+    struct Recorder {
+        events: Vec<DfsEvent<u32>>,
+        stop_at: Option<u32>,
+    }
+
+    impl DfsVisitor<u32> for Recorder {
+        fn discover(&mut self, v: u32) -> Control {
+            self.events.push(DfsEvent::Discover(v));
+            if self.stop_at == Some(v) { Control::Stop } else { Control::Continue }
+        }
+
+        fn finish(&mut self, v: u32) -> Control {
+            self.events.push(DfsEvent::Finish(v));
+            Control::Continue
+        }
+
+        fn tree_edge(&mut self, u: u32, v: u32) -> Control {
+            self.events.push(DfsEvent::TreeEdge(u, v));
+            Control::Continue
+        }
+
+        fn back_edge(&mut self, u: u32, v: u32) -> Control {
+            self.events.push(DfsEvent::BackEdge(u, v));
+            Control::Continue
+        }
+
+        fn forward_edge(&mut self, u: u32, v: u32) -> Control {
+            self.events.push(DfsEvent::ForwardEdge(u, v));
+            Control::Continue
+        }
+
+        fn cross_edge(&mut self, u: u32, v: u32) -> Control {
+            self.events.push(DfsEvent::CrossEdge(u, v));
+            Control::Continue
+        }
+    }
+
+    fn sample() -> GraphMatrix<u32> {
+        GraphMatrix::from_edgelist(vec![(0, 1), (0, 2), (1, 2), (2, 0), (2, 2), (3, 1)]).unwrap()
+    }
+
+    #[test]
+    fn events_classify_every_edge() {
+        use DfsEvent::*;
+        let g = sample();
+        let expected = vec![
+            Discover(0),
+            TreeEdge(0, 1),
+            Discover(1),
+            TreeEdge(1, 2),
+            Discover(2),
+            BackEdge(2, 0),
+            BackEdge(2, 2),
+            Finish(2),
+            Finish(1),
+            ForwardEdge(0, 2),
+            Finish(0),
+            Discover(3),
+            CrossEdge(3, 1),
+            Finish(3),
+        ];
+        assert_eq!(DfsEvents::all(&g).unwrap().collect::<Vec<_>>(), expected);
+        let mut recorder = Recorder {events: Vec::new(), stop_at: None};
+        assert_eq!(dfs_all(&g, &mut recorder).unwrap(), Control::Continue);
+        assert_eq!(recorder.events, expected);
+        assert_eq!(DfsEvents::new(&g, 0).unwrap().collect::<Vec<_>>(), expected[..11]);
+    }
+
+    #[test]
+    fn visitor_can_stop_the_search() {
+        let mut recorder = Recorder {events: Vec::new(), stop_at: Some(2)};
+        assert_eq!(dfs(&sample(), 0, &mut recorder).unwrap(), Control::Stop);
+        assert_eq!(recorder.events.last(), Some(&DfsEvent::Discover(2)));
+        assert_eq!(recorder.events.len(), 5);
+    }
+
+    #[test]
+    fn pre_and_post_order() {
+        let g = sample();
+        assert_eq!(DfsPreorder::new(&g, 0).unwrap().collect::<Vec<_>>(), vec![0, 1, 2]);
+        assert_eq!(DfsPostorder::new(&g, 0).unwrap().collect::<Vec<_>>(), vec![2, 1, 0]);
+        assert_eq!(DfsPreorder::new(&g, 3).unwrap().collect::<Vec<_>>(), vec![3, 1, 2, 0]);
+        assert_eq!(DfsPostorder::new(&g, 3).unwrap().collect::<Vec<_>>(), vec![0, 2, 1, 3]);
+    }
+
+    #[test]
+    fn long_chains_do_not_overflow_the_stack() {
+        let n: u32 = 1_000_000;
+        let g = GraphMatrix::from_edgelist((0..n - 1).map(|v| (v, v + 1)).collect()).unwrap();
+        let mut post = DfsPostorder::new(&g, 0).unwrap();
+        assert_eq!(post.next(), Some(n - 1));
+        assert_eq!(post.count(), n as usize - 1);
+    }
+}
@@ -0,0 +1,256 @@
+//! Triangle counting and clustering coefficients for an undirected graph, stored as a
+//! symmetric GraphMatrix (every edge present in both directions). Self-loops are ignored.
+//!
+//! Each edge is oriented from the endpoint of lower degree to the endpoint of higher degree
+//! (ties broken by id), so every triangle is found exactly once and high-degree vertices keep
+//! short oriented rows. Triangles through an oriented edge `u -> v` are then the common
+//! elements of the two oriented rows, found by merging the sorted rows or, when one is much
+//! longer than the other, by galloping through the longer one.
+
+use num::PrimInt;
+
+use crate::{to_usize, GraphMatrix, GraphMatrixError};
+
+/// How much longer one row must be before intersection switches from a linear merge to
+/// galloping search.
+const GALLOP_RATIO: usize = 32;
+
+/// Calls `f` with every element of `a` that also appears in `b`. Both must be sorted.
+fn intersect<T: Ord + Copy>(a: &[T], b: &[T], mut f: impl FnMut(T)) {
+    let (short, long) = if a.len() <= b.len() { (a, b) } else { (b, a) };
+    if short.is_empty() {
+        return;
+    }
+    if long.len() / short.len() >= GALLOP_RATIO {
+        gallop(short, long, f);
+        return;
+    }
+    let (mut i, mut j) = (0, 0);
+    while i < short.len() && j < long.len() {
+        match short[i].cmp(&long[j]) {
+            std::cmp::Ordering::Less => i += 1,
+            std::cmp::Ordering::Greater => j += 1,
+            std::cmp::Ordering::Equal => {
+                f(short[i]);
+                i += 1;
+                j += 1;
+            }
+        }
+    }
+}
+
+/// Intersects a short sorted slice with a much longer one by exponential search, in
+/// O(|short| log |long|).
+fn gallop<T: Ord + Copy>(short: &[T], long: &[T], mut f: impl FnMut(T)) {
+    let mut base = 0;
+    for &x in short {
+        let mut step = 1;
+        while base + step < long.len() && long[base + step] < x {
+            step *= 2;
+        }
+        let end = (base + step + 1).min(long.len());
+        match long[base..end].binary_search(&x) {
+            Ok(p) => {
+                f(x);
+                base += p + 1;
+            }
+            Err(p) => base += p,
+        }
+        if base >= long.len() {
+            return;
+        }
+    }
+}
+
+/// Keeps only the edges `u -> v` where `v` outranks `u` by `(degree, id)`.
+fn orient<T: PrimInt>(
+    g: &GraphMatrix<T>,
+    degree: &[usize],
+) -> Result<GraphMatrix<T>, GraphMatrixError> {
+    let mut indptr = Vec::with_capacity(degree.len() + 1);
+    let mut indices = Vec::new();
+    indptr.push(0);
+    for (u, &du) in degree.iter().enumerate() {
+        for &v in g.row_slice(u) {
+            let vu = to_usize(v)?;
+            if (du, u) < (degree[vu], vu) {
+                indices.push(v);
+            }
+        }
+        indptr.push(indices.len());
+    }
+    Ok(GraphMatrix {indptr, indices, ncols: degree.len()})
+}
+
+/// Calls `f(u, v, w)` once for every triangle.
+fn for_each_triangle<T: PrimInt>(
+    g: &GraphMatrix<T>,
+    degree: &[usize],
+    mut f: impl FnMut(usize, usize, usize),
+) -> Result<(), GraphMatrixError> {
+    let dag = orient(g, degree)?;
+    for u in 0..degree.len() {
+        let nu = dag.row_slice(u);
+        for &v in nu {
+            let vu = to_usize(v)?;
+            intersect(nu, dag.row_slice(vu), |w| {
+                // every index in the matrix converts, as checked on construction.
+                f(u, vu, w.to_usize().expect("index fits in usize"));
+            });
+        }
+    }
+    Ok(())
+}
+
+/// Returns the number of triangles in `g`.
+pub fn count<T: PrimInt>(g: &GraphMatrix<T>) -> Result<u64, GraphMatrixError> {
+    let mut total = 0;
+    for_each_triangle(g, &g.degrees_without_loops()?, |_, _, _| total += 1)?;
+    Ok(total)
+}
+
+/// Returns the number of triangles through each vertex.
+pub fn per_vertex<T: PrimInt>(g: &GraphMatrix<T>) -> Result<Vec<u64>, GraphMatrixError> {
+    let degree = g.degrees_without_loops()?;
+    let mut tri = vec![0u64; degree.len()];
+    for_each_triangle(g, &degree, |u, v, w| {
+        tri[u] += 1;
+        tri[v] += 1;
+        tri[w] += 1;
+    })?;
+    Ok(tri)
+}
+
+/// Returns each vertex's local clustering coefficient: the fraction of pairs of its
+/// neighbors that are themselves adjacent. Vertices with fewer than two neighbors get zero.
+pub fn local_clustering<T: PrimInt>(g: &GraphMatrix<T>) -> Result<Vec<f64>, GraphMatrixError> {
+    let degree = g.degrees_without_loops()?;
+    let tri = per_vertex(g)?;
+    Ok(degree
+        .iter()
+        .zip(&tri)
+        .map(|(&d, &t)| {
+            if d < 2 {
+                0.0
+            } else {
+                2.0 * t as f64 / (d * (d - 1)) as f64
+            }
+        })
+        .collect())
+}
+
+/// Returns the transitivity of `g`: three times the number of triangles divided by the
+/// number of connected triples (paths of length two). Zero if there are no triples.
+pub fn transitivity<T: PrimInt>(g: &GraphMatrix<T>) -> Result<f64, GraphMatrixError> {
+    let degrees = g.degrees_without_loops()?;
+    let triples: u64 = degrees.iter().map(|&d| (d * d.saturating_sub(1) / 2) as u64).sum();
+    if triples == 0 {
+        return Ok(0.0);
+    }
+    Ok(3.0 * count(g)? as f64 / triples as f64)
+}
+
+#[cfg(test)]
+mod tests {
+    use rand::rngs::StdRng;
+    use rand::{Rng, SeedableRng};
+
+    use super::*;
+    use crate::semiring::{mxm, MatrixMask, PlusTimes};
+
+    /// Stores each undirected edge in both directions.
+    fn undirected(n: usize, edges: &[(u32, u32)]) -> GraphMatrix<u32> {
+        let both = edges.iter().flat_map(|&(u, v)| [(u, v), (v, u)]).collect();
+        GraphMatrix::from_edgelist_with_dims(both, n, n).unwrap()
+    }
+
+    fn complete(n: u32) -> Vec<(u32, u32)> {
+        (0..n).flat_map(|u| (u + 1..n).map(move |v| (u, v))).collect()
+    }
+
+    fn random_graph(n: u32, m: usize, seed: u64) -> GraphMatrix<u32> {
+        let mut rng = StdRng::seed_from_u64(seed);
+        let edges: Vec<_> = (0..m)
+            .map(|_| (rng.gen_range(0..n), rng.gen_range(0..n)))
+            .filter(|(u, v)| u != v)
+            .collect();
+        undirected(n as usize, &edges)
+    }
+
+    #[test]
+    fn complete_graphs() {
+        let k4 = undirected(4, &complete(4));
+        assert_eq!(count(&k4).unwrap(), 4);
+        assert_eq!(per_vertex(&k4).unwrap(), vec![3; 4]);
+        assert_eq!(local_clustering(&k4).unwrap(), vec![1.0; 4]);
+        assert_eq!(transitivity(&k4).unwrap(), 1.0);
+        assert_eq!(count(&undirected(10, &complete(10))).unwrap(), 120);
+    }
+
+    #[test]
+    fn self_loops_are_ignored() {
+        let mut edges = complete(4);
+        edges.extend((0..4).map(|v| (v, v)));
+        let g = undirected(4, &edges);
+        assert_eq!(count(&g).unwrap(), 4);
+        assert_eq!(local_clustering(&g).unwrap(), vec![1.0; 4]);
+    }
+
+    #[test]
+    fn bipartite_graphs_have_no_triangles() {
+        let edges: Vec<_> = (0..3).flat_map(|u| (3..6).map(move |v| (u, v))).collect();
+        let g = undirected(6, &edges);
+        assert_eq!(count(&g).unwrap(), 0);
+        assert_eq!(local_clustering(&g).unwrap(), vec![0.0; 6]);
+        assert_eq!(transitivity(&g).unwrap(), 0.0);
+    }
+
+    #[test]
+    fn clustering_on_a_star() {
+        let mut edges: Vec<_> = (1..5).map(|v| (0, v)).collect();
+        let g = undirected(5, &edges);
+        assert_eq!(local_clustering(&g).unwrap(), vec![0.0; 5]);
+        edges.push((1, 2));
+        let g = undirected(5, &edges);
+        assert_eq!(local_clustering(&g).unwrap(), vec![1.0 / 6.0, 1.0, 1.0, 0.0, 0.0]);
+        // one triangle and 6 + 1 + 1 triples
+        assert_eq!(transitivity(&g).unwrap(), 3.0 / 8.0);
+    }
+
+    #[test]
+    fn galloping_matches_merging() {
+        let mut rng = StdRng::seed_from_u64(16);
+        for _ in 0..20 {
+            let mut long: Vec<u32> = (0..2000).map(|_| rng.gen_range(0..5000)).collect();
+            long.sort_unstable();
+            long.dedup();
+            // every 300th element skews the lengths enough to gallop, every 20th does not.
+            for step in [300, 20] {
+                let mut short: Vec<u32> = (0..10).map(|_| rng.gen_range(0..5000)).collect();
+                short.extend(long.iter().step_by(step));
+                short.sort_unstable();
+                short.dedup();
+                let expected: Vec<u32> =
+                    short.iter().copied().filter(|x| long.contains(x)).collect();
+                let (mut found, mut galloped) = (Vec::new(), Vec::new());
+                intersect(&long, &short, |x| found.push(x));
+                gallop(&short, &long, |x| galloped.push(x));
+                assert_eq!(found, expected);
+                assert_eq!(galloped, expected);
+            }
+        }
+    }
+
+    #[test]
+    fn count_matches_a_masked_product() {
+        for seed in 0..4 {
+            let g = random_graph(200, 2000, seed);
+            // (A·A)[u][v] over the edges of A counts each triangle six times.
+            let paths = mxm(&g, &g, &PlusTimes::<u64>::new(), Some(MatrixMask::new(&g))).unwrap();
+            let total: u64 = paths.values().iter().sum();
+            assert_eq!(count(&g).unwrap(), total / 6);
+            let tri = per_vertex(&g).unwrap();
+            assert_eq!(tri.iter().sum::<u64>(), total / 2);
+        }
+    }
+}
@@ -0,0 +1,233 @@
+use std::ops::Add;
+
+use num::PrimInt;
+
+use crate::{compress, edge_dims, GraphMatrix, GraphMatrixError};
+
+/// How values attached to duplicate `(row, col)` entries are combined when building a
+/// `ValuedGraphMatrix`. Policies apply to values that can be compared and added; for any
+/// other values, pass a merge function to `ValuedGraphMatrix::from_edgelist_by`.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum DuplicatePolicy {
+    Sum,
+    Min,
+    Max,
+    /// Keep the value that appeared first in the input.
+    First,
+    /// Keep the value that appeared last in the input.
+    Last,
+}
+
+impl DuplicatePolicy {
+    fn combine<V>(self, acc: V, v: V) -> V
+    where
+        V: PartialOrd + Add<Output = V>,
+    {
+        match self {
+            DuplicatePolicy::Sum => acc + v,
+            DuplicatePolicy::Min => if v < acc { v } else { acc },
+            DuplicatePolicy::Max => if v > acc { v } else { acc },
+            DuplicatePolicy::First => acc,
+            DuplicatePolicy::Last => v,
+        }
+    }
+}
+
+/// A ValuedGraphMatrix is a GraphMatrix with a value vector running parallel to its
+/// indices, so `values[i]` belongs to the element stored at `indices[i]`.
+#[derive(Debug)]
+pub struct ValuedGraphMatrix<T, V> {
+    graph: GraphMatrix<T>,
+    values: Vec<V>,
+}
+
+impl<T, V> ValuedGraphMatrix<T, V> where T: PrimInt {
+
+    /// Attaches `values` to an existing structure. `values` must have one entry per element
+    /// of `graph`, in the order of its indices.
+    pub fn try_from_parts(graph: GraphMatrix<T>, values: Vec<V>) -> Result<Self, GraphMatrixError> {
+        if values.len() != graph.ne() {
+            return Err(GraphMatrixError::DimensionMismatch {expected: graph.ne(), found: values.len()})
+        }
+        Ok(ValuedGraphMatrix {graph, values})
+    }
+
+    /// The underlying (value-less) structure.
+    pub fn graph(&self) -> &GraphMatrix<T> {
+        &self.graph
+    }
+
+    /// All values, in the same order as the underlying indices.
+    pub fn values(&self) -> &[V] {
+        &self.values
+    }
+
+    pub fn dims(&self) -> (usize, usize) {
+        self.graph.dims()
+    }
+
+    pub fn ne(&self) -> usize {
+        self.graph.ne()
+    }
+
+    pub fn row(&self, r: T) -> Result<&[T], GraphMatrixError> {
+        self.graph.row(r)
+    }
+
+    pub fn row_values(&self, r: T) -> Result<&[V], GraphMatrixError> {
+        Ok(&self.values[self.graph.row_range(r)?])
+    }
+
+    pub fn row_len(&self, r: T) -> Result<usize, GraphMatrixError> {
+        self.graph.row_len(r)
+    }
+
+    pub fn has_index(&self, r: T, c: T) -> Result<bool, GraphMatrixError> {
+        self.graph.has_index(r, c)
+    }
+
+    /// Returns the value stored at `(r, c)`, or `None` if there is no such element.
+    pub fn get(&self, r: T, c: T) -> Option<&V> {
+        let range = self.graph.row_range(r).ok()?;
+        let offset = self.graph.indices[range.clone()].binary_search(&c).ok()?;
+        self.values.get(range.start + offset)
+    }
+
+    /// Splits the matrix into its structure and its values.
+    pub fn into_parts(self) -> (GraphMatrix<T>, Vec<V>) {
+        (self.graph, self.values)
+    }
+}
+
+impl<T, V> ValuedGraphMatrix<T, V>
+where
+    T: PrimInt,
+    V: PartialOrd + Add<Output = V>,
+{
+    /// Builds a square ValuedGraphMatrix from `(row, col, value)` triples. Values for
+    /// repeated `(row, col)` pairs are merged according to `policy`.
+    pub fn from_edgelist(
+        edgelist: Vec<(T, T, V)>,
+        policy: DuplicatePolicy,
+    ) -> Result<Self, GraphMatrixError> {
+        Self::from_edgelist_by(edgelist, |acc, v| policy.combine(acc, v))
+    }
+
+    /// Builds an `nrows` x `ncols` ValuedGraphMatrix. Returns `GraphMatrixError::BoundsError`
+    /// if any index in `edgelist` falls outside those dimensions.
+    pub fn from_edgelist_with_dims(
+        edgelist: Vec<(T, T, V)>,
+        nrows: usize,
+        ncols: usize,
+        policy: DuplicatePolicy,
+    ) -> Result<Self, GraphMatrixError> {
+        Self::from_edgelist_with_dims_by(edgelist, nrows, ncols, |acc, v| policy.combine(acc, v))
+    }
+}
+
+impl<T, V> ValuedGraphMatrix<T, V> where T: PrimInt {
+
+    /// Like `from_edgelist`, but values for a repeated `(row, col)` pair are folded together
+    /// in input order with `merge`. This places no bounds on `V`: `|acc, _| acc` keeps the
+    /// first value of any type.
+    pub fn from_edgelist_by<F>(edgelist: Vec<(T, T, V)>, merge: F) -> Result<Self, GraphMatrixError>
+    where
+        F: FnMut(V, V) -> V,
+    {
+        let (nrows, ncols) = edge_dims(edgelist.iter().map(|&(s, d, _)| (s, d)), None)?;
+        let (ss, ds, vs) = Self::merged_edges(edgelist, merge);
+        Self::from_merged_edges(ss, ds, vs, nrows, ncols)
+    }
+
+    /// Like `from_edgelist_with_dims`, but with duplicates merged as in `from_edgelist_by`.
+    pub fn from_edgelist_with_dims_by<F>(
+        edgelist: Vec<(T, T, V)>,
+        nrows: usize,
+        ncols: usize,
+        merge: F,
+    ) -> Result<Self, GraphMatrixError>
+    where
+        F: FnMut(V, V) -> V,
+    {
+        edge_dims(edgelist.iter().map(|&(s, d, _)| (s, d)), Some((nrows, ncols)))?;
+        let (ss, ds, vs) = Self::merged_edges(edgelist, merge);
+        Self::from_merged_edges(ss, ds, vs, nrows, ncols)
+    }
+
+    fn merged_edges<F>(edgelist: Vec<(T, T, V)>, mut merge: F) -> (Vec<T>, Vec<T>, Vec<V>)
+    where
+        F: FnMut(V, V) -> V,
+    {
+        let mut sorted_edgelist = edgelist;
+        // stable, so merging follows input order.
+        sorted_edgelist.sort_by_key(|&(s, d, _)| (s, d));
+
+        let mut ss: Vec<T> = Vec::with_capacity(sorted_edgelist.len());
+        let mut ds: Vec<T> = Vec::with_capacity(sorted_edgelist.len());
+        let mut vs: Vec<V> = Vec::with_capacity(sorted_edgelist.len());
+        for (s, d, v) in sorted_edgelist {
+            if ss.last() == Some(&s) && ds.last() == Some(&d) {
+                let acc = vs.pop().unwrap();
+                vs.push(merge(acc, v));
+            } else {
+                ss.push(s);
+                ds.push(d);
+                vs.push(v);
+            }
+        }
+        (ss, ds, vs)
+    }
+
+    fn from_merged_edges(
+        ss: Vec<T>,
+        ds: Vec<T>,
+        vs: Vec<V>,
+        nrows: usize,
+        ncols: usize,
+    ) -> Result<Self, GraphMatrixError> {
+        let (indptr, indices, values) = compress(ss, ds, vs, nrows)?;
+        Ok(ValuedGraphMatrix {graph: GraphMatrix {indptr, indices, ncols}, values})
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn values_follow_their_elements() {
+        let edges = vec![(2, 0, 20.0), (0, 1, 1.0), (1, 2, 12.0), (0, 0, 0.5), (2, 1, 21.0)];
+        let g = ValuedGraphMatrix::<u32, f64>::from_edgelist(edges, DuplicatePolicy::Sum).unwrap();
+        let elements: Vec<_> = g.graph().iter().zip(g.values()).collect();
+        let expected = [(0, 0), (0, 1), (1, 2), (2, 0), (2, 1)];
+        let values = [0.5, 1.0, 12.0, 20.0, 21.0];
+        assert_eq!(elements, expected.into_iter().zip(&values).collect::<Vec<_>>());
+    }
+
+    #[test]
+    fn policies_merge_duplicates() {
+        let edges = vec![(0, 1, 3), (1, 0, 7), (0, 1, 1), (0, 1, 2)];
+        let merged = |policy| {
+            let g = ValuedGraphMatrix::<u8, i32>::from_edgelist(edges.clone(), policy).unwrap();
+            *g.get(0, 1).unwrap()
+        };
+        assert_eq!(merged(DuplicatePolicy::Sum), 6);
+        assert_eq!(merged(DuplicatePolicy::Min), 1);
+        assert_eq!(merged(DuplicatePolicy::Max), 3);
+        assert_eq!(merged(DuplicatePolicy::First), 3);
+        assert_eq!(merged(DuplicatePolicy::Last), 2);
+    }
+
+    #[test]
+    fn merge_function_needs_no_bounds() {
+        /// Neither comparable, addable nor cloneable.
+        #[derive(Debug, PartialEq)]
+        struct Label(&'static str);
+
+        let edges = vec![(1, 0, Label("b")), (0, 1, Label("a")), (1, 0, Label("c"))];
+        let g = ValuedGraphMatrix::<u16, Label>::from_edgelist_with_dims_by(edges, 2, 3, |_, v| v)
+            .unwrap();
+        assert_eq!(g.dims(), (2, 3));
+        assert_eq!(g.values(), &[Label("a"), Label("c")]);
+    }
+}
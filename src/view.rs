@@ -0,0 +1,116 @@
+use num::PrimInt;
+
+use crate::{to_usize, validate_csr, GraphMatrix, GraphMatrixError, GraphMatrixIterator};
+
+mod sealed {
+    pub trait Sealed {}
+}
+
+/// The primitive integer types, whose values can be read straight out of a byte buffer. This
+/// is what lets a GraphMatrixView sit directly on a memory-mapped file.
+pub trait PlainIndex: PrimInt + sealed::Sealed {}
+
+macro_rules! plain_index {
+    ($($t:ty),*) => {
+        $(
+            impl sealed::Sealed for $t {}
+            impl PlainIndex for $t {}
+        )*
+    };
+}
+
+plain_index!(u8, u16, u32, u64, u128, usize, i8, i16, i32, i64, i128, isize);
+
+/// A read-only GraphMatrix over borrowed CSR arrays, which may live in a memory-mapped file
+/// (see `GraphMatrixView::from_bytes`) or in a GraphMatrix (see `GraphMatrix::view`). It
+/// supports the same queries as a GraphMatrix without copying anything.
+#[derive(Debug, Clone, Copy)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize), serde(rename = "GraphMatrix"))]
+pub struct GraphMatrixView<'a, T> {
+    pub(crate) indptr: &'a [usize],
+    pub(crate) indices: &'a [T],
+    pub(crate) ncols: usize,
+}
+
+impl<'a, T> GraphMatrixView<'a, T> where T: PrimInt {
+
+    /// Creates a view over existing CSR arrays, checking that they are well-formed.
+    pub fn try_from_csr(
+        indptr: &'a [usize],
+        indices: &'a [T],
+        ncols: usize,
+    ) -> Result<Self, GraphMatrixError> {
+        validate_csr(indptr, indices, ncols)?;
+        Ok(GraphMatrixView {indptr, indices, ncols})
+    }
+
+    /// Creates a view over existing CSR arrays without checking them.
+    ///
+    /// # Safety
+    ///
+    /// The arrays must satisfy the same invariants `try_from_csr` checks.
+    pub unsafe fn from_csr_unchecked(indptr: &'a [usize], indices: &'a [T], ncols: usize) -> Self {
+        GraphMatrixView {indptr, indices, ncols}
+    }
+
+    /// Returns `(nrows, ncols)`.
+    pub fn dims(&self) -> (usize, usize) {
+        (self.indptr.len() - 1, self.ncols)
+    }
+
+    pub fn ne(&self) -> usize {
+        self.indices.len()
+    }
+
+    /// The row pointer array: row `r` occupies `indices()[indptr()[r]..indptr()[r + 1]]`.
+    pub fn indptr(&self) -> &'a [usize] {
+        self.indptr
+    }
+
+    /// The column indices of every element, row by row.
+    pub fn indices(&self) -> &'a [T] {
+        self.indices
+    }
+
+    pub fn row(&self, r: T) -> Result<&'a [T], GraphMatrixError> {
+        let ru = to_usize(r)?;
+        let nrows = self.indptr.len() - 1;
+        if ru >= nrows {
+            return Err(GraphMatrixError::BoundsError {index: ru, bound: nrows, edge: None})
+        }
+        Ok(&self.indices[self.indptr[ru]..self.indptr[ru + 1]])
+    }
+
+    pub fn row_len(&self, r: T) -> Result<usize, GraphMatrixError> {
+        Ok(self.row(r)?.len())
+    }
+
+    pub fn has_index(&self, r: T, c: T) -> Result<bool, GraphMatrixError> {
+        Ok(self.row(r)?.binary_search(&c).is_ok())
+    }
+
+    /// Iterates over every `(row, col)` element in row-major order.
+    pub fn iter(&self) -> GraphMatrixIterator<'a, T> {
+        GraphMatrixIterator::from_view(*self)
+    }
+
+    /// Copies the viewed arrays into an owned GraphMatrix, which every algorithm accepts.
+    pub fn to_graph_matrix(&self) -> GraphMatrix<T> {
+        let (indptr, indices) = (self.indptr.to_vec(), self.indices.to_vec());
+        GraphMatrix {indptr, indices, ncols: self.ncols}
+    }
+}
+
+impl<'a, T> IntoIterator for GraphMatrixView<'a, T> where T: PrimInt {
+    type Item = (T, T);
+    type IntoIter = GraphMatrixIterator<'a, T>;
+    fn into_iter(self) -> Self::IntoIter {
+        GraphMatrixIterator::from_view(self)
+    }
+}
+
+impl<'a, T> From<GraphMatrixView<'a, T>> for GraphMatrix<T> where T: PrimInt {
+    fn from(view: GraphMatrixView<'a, T>) -> Self {
+        view.to_graph_matrix()
+    }
+}